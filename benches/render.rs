@@ -0,0 +1,28 @@
+//! One full frame of the UI against ratatui's `TestBackend`, with the mock
+//! backend's readings and enough ticks behind it to fill the history widgets.
+
+use std::hint::black_box;
+use std::time::Duration;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use gtop::app::App;
+use gtop::backend::FakeBackend;
+use gtop::ui::ui;
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+
+fn render(c: &mut Criterion) {
+    let mut app = App::new(Box::new(FakeBackend::new()), Duration::from_millis(500), Duration::from_secs(120)).unwrap();
+    for _ in 0..120 {
+        app.on_tick();
+    }
+    let mut terminal = Terminal::new(TestBackend::new(160, 50)).unwrap();
+    c.bench_function("render/full_frame", |b| {
+        b.iter(|| {
+            terminal.draw(|f| ui(f, black_box(&app))).unwrap();
+        })
+    });
+}
+
+criterion_group!(benches, render);
+criterion_main!(benches);
@@ -0,0 +1,73 @@
+//! Cost of one `sample()` call per backend, to catch per-tick allocations or
+//! parsing creeping into the sampling path.
+//!
+//! The AMD backend runs against a fixture tree shaped like `/sys/class/drm`
+//! (two cards with the usual amdgpu and hwmon nodes), so it works anywhere.
+
+use std::fs;
+use std::hint::black_box;
+use std::path::{Path, PathBuf};
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use gtop::backend::{AmdSysfsBackend, FakeBackend, GpuBackend};
+
+fn write_all(dir: &Path, files: &[(&str, &str)]) {
+    fs::create_dir_all(dir).unwrap();
+    for (name, value) in files {
+        fs::write(dir.join(name), format!("{value}\n")).unwrap();
+    }
+}
+
+/// A throwaway DRM class directory with `cards` amdgpu cards in it.
+fn amd_fixture(cards: usize) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("gtop-bench-drm-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    for n in 0..cards {
+        let device = root.join(format!("card{n}")).join("device");
+        write_all(
+            &device,
+            &[
+                ("vendor", "0x1002"),
+                ("product_name", "AMD Radeon RX 7900 XTX"),
+                ("gpu_busy_percent", "37"),
+                ("mem_info_vram_used", "2147483648"),
+                ("mem_info_vram_total", "25769803776"),
+                ("pp_dpm_sclk", "0: 500Mhz\n1: 1800Mhz *\n2: 2500Mhz"),
+                ("pp_dpm_mclk", "0: 96Mhz\n1: 1250Mhz *"),
+                ("current_link_speed", "16.0 GT/s PCIe"),
+                ("current_link_width", "16"),
+                ("vbios_version", "113-D7020100-102"),
+            ],
+        );
+        write_all(
+            &device.join("hwmon").join("hwmon0"),
+            &[
+                ("temp1_input", "55000"),
+                ("temp2_input", "68000"),
+                ("temp3_input", "60000"),
+                ("temp1_crit", "100000"),
+                ("power1_average", "95000000"),
+                ("power1_cap", "303000000"),
+                ("fan1_input", "1300"),
+                ("fan1_max", "3300"),
+                ("pwm1", "90"),
+                ("freq1_input", "1800000000"),
+                ("freq2_input", "1250000000"),
+            ],
+        );
+    }
+    root
+}
+
+fn backends(c: &mut Criterion) {
+    let mut fake = FakeBackend::new();
+    c.bench_function("sample/mock", |b| b.iter(|| black_box(fake.sample().unwrap())));
+
+    let root = amd_fixture(2);
+    let mut amd = AmdSysfsBackend::with_root(&root).unwrap();
+    c.bench_function("sample/amd_fixture", |b| b.iter(|| black_box(amd.sample().unwrap())));
+    fs::remove_dir_all(root).unwrap();
+}
+
+criterion_group!(benches, backends);
+criterion_main!(benches);
@@ -0,0 +1,1675 @@
+//! `App`: sampling loop state, per-GPU history, alerts and key handling.
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+use clap::ValueEnum;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::Rect;
+use serde::Deserialize;
+
+use crate::backend::{BackendHealth, GpuBackend};
+use crate::backend::replay::Recorder;
+use crate::config::{AlertConfig, Preferences};
+use crate::export::{CsvLogger, write_snapshot};
+use crate::metrics::{GpuMetrics, VramUnit};
+use crate::sampler::Sampler;
+use crate::server::{SharedMetrics, SocketFile};
+use crate::style::{CardHealth, TempUnit, Theme, ThemeName, Thresholds};
+use crate::ui::fmt_dec;
+
+/// How long a footer flash message stays up.
+const FLASH_DURATION: Duration = Duration::from_secs(3);
+/// Longer for the permission hint: it's only ever shown once.
+const HINT_DURATION: Duration = Duration::from_secs(15);
+
+/// Short-lived footer message ("snapshot written to ...", etc.).
+pub struct Flash {
+    pub text: String,
+    pub error: bool,
+    at: Instant,
+    duration: Duration,
+}
+
+/// Entries kept in the `a` alert log; older ones fall off.
+const ALERT_LOG_LEN: usize = 100;
+
+/// A threshold crossing (or the recovery from one), for the alert log.
+pub struct AlertEvent {
+    pub at: SystemTime,
+    pub text: String,
+    /// Going over, as opposed to coming back under.
+    pub critical: bool,
+}
+
+/// `o` cycles through these. Everything but `Device` puts the highest reading
+/// first; GPUs without that reading go last, in device order.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortKey {
+    #[default]
+    Device,
+    Temperature,
+    Utilization,
+    Vram,
+    Power,
+}
+
+impl SortKey {
+    fn next(self) -> Self {
+        match self {
+            SortKey::Device => SortKey::Temperature,
+            SortKey::Temperature => SortKey::Utilization,
+            SortKey::Utilization => SortKey::Vram,
+            SortKey::Vram => SortKey::Power,
+            SortKey::Power => SortKey::Device,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortKey::Device => "device",
+            SortKey::Temperature => "temperature",
+            SortKey::Utilization => "utilization",
+            SortKey::Vram => "VRAM",
+            SortKey::Power => "power",
+        }
+    }
+
+    fn value(self, gpu: &GpuMetrics) -> Option<f64> {
+        match self {
+            SortKey::Device => None,
+            SortKey::Temperature => gpu.temperature_c.map(f64::from),
+            SortKey::Utilization => gpu.utilization_pct.map(f64::from),
+            SortKey::Vram => gpu.vram_used_mb.map(|mb| mb as f64),
+            SortKey::Power => gpu.power_w.map(f64::from),
+        }
+    }
+}
+
+/// `--filter`: which GPUs get drawn at all.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GpuFilter {
+    #[default]
+    All,
+    /// Hide GPUs under `--busy-threshold` utilization (unknown utilization stays visible).
+    Busy,
+}
+
+/// `--layout`: how a GPU panel arranges its gauges.
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum GaugeLayout {
+    /// One gauge per row
+    #[default]
+    Vertical,
+    /// Utilization and VRAM share a row, fan, power and clocks the next
+    Horizontal,
+    /// Horizontal on wide terminals, vertical otherwise
+    Auto,
+}
+
+/// `--redraw`: when the run loop repaints the screen.
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RedrawMode {
+    /// Every tick, so the elapsed time and pause counter keep moving
+    #[default]
+    Always,
+    /// Only after a sample that differs from the last one, or any input
+    OnChange,
+}
+
+/// `--gauge-labels` / `g`: where a gauge's reading goes.
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum GaugeLabels {
+    /// Over the bar
+    #[default]
+    Bar,
+    /// In the gauge's frame title, leaving the bar clear
+    Title,
+    /// Nowhere; the text block has the numbers
+    Off,
+}
+
+impl GaugeLabels {
+    fn next(self) -> Self {
+        match self {
+            GaugeLabels::Bar => GaugeLabels::Title,
+            GaugeLabels::Title => GaugeLabels::Off,
+            GaugeLabels::Off => GaugeLabels::Bar,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GaugeLabels::Bar => "on the bar",
+            GaugeLabels::Title => "in the title",
+            GaugeLabels::Off => "off",
+        }
+    }
+}
+
+/// `--separator` / `b`: what goes between GPU rows in compact mode and in the
+/// focused view's summary lines (full panels have their borders).
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum GpuSeparator {
+    /// Rows back to back, so the most GPUs fit
+    #[default]
+    None,
+    /// An empty line
+    Blank,
+    /// A line of dashes
+    Rule,
+}
+
+impl GpuSeparator {
+    fn next(self) -> Self {
+        match self {
+            GpuSeparator::None => GpuSeparator::Blank,
+            GpuSeparator::Blank => GpuSeparator::Rule,
+            GpuSeparator::Rule => GpuSeparator::None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GpuSeparator::None => "none",
+            GpuSeparator::Blank => "blank line",
+            GpuSeparator::Rule => "rule",
+        }
+    }
+}
+
+/// The gauges a GPU panel can show; `1`-`5` toggle them, in this order.
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum GaugeKind {
+    Util,
+    Vram,
+    Fan,
+    Power,
+    Clocks,
+}
+
+impl GaugeKind {
+    pub const ALL: [GaugeKind; 5] =
+        [GaugeKind::Util, GaugeKind::Vram, GaugeKind::Fan, GaugeKind::Power, GaugeKind::Clocks];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GaugeKind::Util => "utilization",
+            GaugeKind::Vram => "VRAM",
+            GaugeKind::Fan => "fan",
+            GaugeKind::Power => "power",
+            GaugeKind::Clocks => "clock",
+        }
+    }
+}
+
+/// Which gauges are switched on (`[ui] gauges`, the `1`-`5` keys). All of them by default.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(from = "Vec<GaugeKind>")]
+pub struct GaugeSet(u8);
+
+impl GaugeSet {
+    pub fn contains(self, kind: GaugeKind) -> bool {
+        self.0 & 1 << kind as u8 != 0
+    }
+
+    pub fn toggle(&mut self, kind: GaugeKind) {
+        self.0 ^= 1 << kind as u8;
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = GaugeKind> {
+        GaugeKind::ALL.into_iter().filter(move |&k| self.contains(k))
+    }
+}
+
+impl Default for GaugeSet {
+    fn default() -> Self {
+        GaugeKind::ALL.into_iter().collect::<Vec<_>>().into()
+    }
+}
+
+impl From<Vec<GaugeKind>> for GaugeSet {
+    fn from(kinds: Vec<GaugeKind>) -> Self {
+        GaugeSet(kinds.into_iter().fold(0, |bits, k| bits | 1 << k as u8))
+    }
+}
+
+/// Which way a reading moved between the last two samples.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trend {
+    Rising,
+    Falling,
+    Steady,
+}
+
+impl Trend {
+    /// Sensors report whole degrees (or millidegrees that jitter), so tiny
+    /// moves count as holding.
+    fn between(prev: f32, now: f32) -> Self {
+        const DEADBAND: f32 = 0.1;
+        if now > prev + DEADBAND {
+            Trend::Rising
+        } else if now < prev - DEADBAND {
+            Trend::Falling
+        } else {
+            Trend::Steady
+        }
+    }
+}
+
+/// Rolling per-GPU history feeding the sparkline/chart. Missing readings are
+/// skipped rather than recorded as zero.
+#[derive(Debug, Default)]
+pub struct GpuHistory {
+    /// Utilization, oldest first, capped at `App::history_len` samples.
+    pub util: VecDeque<f32>,
+    /// Core temp (Celsius) with sample time; anything older than the window is dropped.
+    pub temp: VecDeque<(Instant, f32)>,
+    /// Power draw (W), oldest first, capped like `util`.
+    pub power: VecDeque<f32>,
+    /// VRAM in use (MB), oldest first, capped like `util`.
+    pub vram: VecDeque<u32>,
+    /// Core / memory clocks (MHz), capped like `util`. Unlike the others a
+    /// missing reading is kept as `None`, so boost and throttle dips stay
+    /// where they happened in time.
+    pub core_clock: VecDeque<Option<u32>>,
+    pub mem_clock: VecDeque<Option<u32>>,
+    /// Session-wide power stats, unlike the capped buffer above.
+    power_sum: f64,
+    power_samples: u64,
+    pub power_peak: Option<f32>,
+    /// Core temp against the previous sample; `None` until there are two readings.
+    pub temp_trend: Option<Trend>,
+    /// Power went critical and hasn't dropped back under warn yet (alert log state).
+    pub over_power: bool,
+}
+
+fn push_capped(buf: &mut VecDeque<f32>, v: f32, cap: usize) {
+    buf.push_back(v);
+    trim_front(buf, cap);
+}
+
+fn trim_front<T>(buf: &mut VecDeque<T>, cap: usize) {
+    let excess = buf.len().saturating_sub(cap);
+    buf.drain(..excess);
+}
+
+impl GpuHistory {
+    fn push(&mut self, gpu: &GpuMetrics, window: Duration, cap: usize) {
+        if let Some(util) = gpu.utilization_pct {
+            push_capped(&mut self.util, util, cap);
+        }
+
+        if let Some(used) = gpu.vram_used_mb {
+            self.vram.push_back(used);
+            trim_front(&mut self.vram, cap);
+        }
+
+        self.core_clock.push_back(gpu.core_clock_mhz);
+        trim_front(&mut self.core_clock, cap);
+        self.mem_clock.push_back(gpu.mem_clock_mhz);
+        trim_front(&mut self.mem_clock, cap);
+
+        if let Some(p) = gpu.power_w {
+            push_capped(&mut self.power, p, cap);
+            self.power_sum += p as f64;
+            self.power_samples += 1;
+            self.power_peak = Some(self.power_peak.map_or(p, |peak| peak.max(p)));
+        }
+
+        self.temp_trend = None;
+        if let Some(temp) = gpu.temperature_c {
+            self.temp_trend = self.temp.back().map(|&(_, prev)| Trend::between(prev, temp));
+            self.temp.push_back((gpu.timestamp, temp));
+        }
+        if let Some(cutoff) = gpu.timestamp.checked_sub(window) {
+            while self.temp.front().is_some_and(|&(t, _)| t < cutoff) {
+                self.temp.pop_front();
+            }
+        }
+    }
+
+    /// Mean of the last `n` utilization readings.
+    pub fn util_mean(&self, n: usize) -> Option<f32> {
+        let n = n.clamp(1, self.util.len().max(1));
+        let recent = self.util.iter().rev().take(n);
+        (!self.util.is_empty()).then(|| recent.sum::<f32>() / n as f32)
+    }
+
+    /// `util` with each point replaced by the mean of it and up to `n - 1`
+    /// before it, so the start of the buffer isn't dragged toward zero.
+    pub fn util_smoothed(&self, n: usize) -> Vec<f32> {
+        let n = n.max(1);
+        let util: Vec<f32> = self.util.iter().copied().collect();
+        (0..util.len())
+            .map(|i| {
+                let window = &util[(i + 1).saturating_sub(n)..=i];
+                window.iter().sum::<f32>() / window.len() as f32
+            })
+            .collect()
+    }
+
+    /// Mean of the samples that actually had a reading.
+    pub fn power_avg(&self) -> Option<f32> {
+        (self.power_samples > 0).then(|| (self.power_sum / self.power_samples as f64) as f32)
+    }
+}
+
+/// How long a GPU that stopped reporting stays on screen as "disconnected".
+const DISCONNECT_GRACE: Duration = Duration::from_secs(5);
+
+/// Identity used to match GPUs across samples: the name plus which occurrence of
+/// that name it is, since backends don't give us anything stabler (two identical
+/// cards just come out as `(name, 0)` and `(name, 1)`).
+fn gpu_keys(metrics: &[GpuMetrics]) -> Vec<(String, usize)> {
+    let mut keys: Vec<(String, usize)> = vec![];
+    for gpu in metrics {
+        let nth = keys.iter().filter(|(n, _)| *n == gpu.name).count();
+        keys.push((gpu.name.clone(), nth));
+    }
+    keys
+}
+
+/// Runtime `[` / `]` limits for the history window.
+const MIN_HISTORY_SECS: u64 = 5;
+const MAX_HISTORY_SECS: u64 = 3600;
+
+/// Sampling interval bounds, for `--interval` and the runtime `+` / `-` keys.
+pub const MIN_INTERVAL_MS: u64 = 50;
+const MAX_INTERVAL_MS: u64 = 10_000;
+
+/// How long a tick waits on the sampling thread before moving on without a
+/// fresh sample, and (longer) how long the very first sample gets.
+const MAX_SAMPLE_WAIT: Duration = Duration::from_millis(200);
+const FIRST_SAMPLE_WAIT: Duration = Duration::from_secs(5);
+
+/// What the UI and headless mode say when the backend answers with no cards.
+pub const NO_GPUS: &str = "No GPUs detected — try --backend or run gtop --doctor";
+
+/// Sample-to-sample gaps averaged for the footer's measured interval.
+const SAMPLE_GAP_WINDOW: usize = 20;
+
+/// Readings older than this many refresh intervals are shown as stale.
+const STALE_AFTER_INTERVALS: u32 = 2;
+
+/// `d` cycles the display precision through `0..=MAX_PRECISION`.
+pub const MAX_PRECISION: usize = 2;
+
+/// For the header. Linux exposes it in procfs; elsewhere ask `hostname(1)`.
+fn hostname() -> Option<String> {
+    let name = match std::fs::read_to_string("/proc/sys/kernel/hostname") {
+        Ok(name) => name,
+        Err(_) => {
+            let out = std::process::Command::new("hostname").output().ok()?;
+            String::from_utf8(out.stdout).ok()?
+        }
+    };
+    let name = name.trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+pub struct App {
+    pub running: bool,
+    pub tick: u64,
+    /// When gtop started, for the footer's elapsed clock.
+    pub start: Instant,
+    /// `start` on the wall clock, to put times of day on `Instant`s.
+    pub started_at: SystemTime,
+    /// Samples actually received; `tick` also counts the ones that never came.
+    pub samples: u64,
+    /// `samples` since `r` last reset the measurements, for the footer.
+    pub samples_since_reset: u64,
+    pub metrics: Vec<GpuMetrics>,
+    sampler: Sampler,
+    /// Machine we're reporting on, shown in the header next to the backend.
+    pub host: Option<String>,
+    pub tick_rate: Duration,
+    /// Frozen display: ticks still arrive but metrics aren't replaced.
+    pub paused: bool,
+    /// `--pause-on-blur`: stop sampling and drawing while the terminal window
+    /// is in the background, and `blurred` while it is.
+    pub pause_on_blur: bool,
+    pub blurred: bool,
+    /// When `paused` was last switched on, so the footer can show how long we've been frozen.
+    pub paused_at: Option<Instant>,
+    pub temp_unit: TempUnit,
+    pub vram_unit: VramUnit,
+    pub thresholds: Thresholds,
+    pub card_health: CardHealth,
+    pub theme: Theme,
+    /// Which preset `theme` was built from, for saving it back to the config.
+    pub theme_name: ThemeName,
+    /// Index into `metrics` of the GPU shown in detail / driving the history widgets.
+    pub selected: usize,
+    /// Display order only; `selected` and the per-GPU vecs stay in device order.
+    pub sort: SortKey,
+    pub filter: GpuFilter,
+    /// `--top` / `n` / `N`: only the first this many of the sorted GPUs get
+    /// drawn, re-ranked every tick; the rest share one summary line.
+    pub top_n: Option<usize>,
+    /// `--gpus`: backend indices to keep, ascending. Unlike `filter` the rest
+    /// never make it into `metrics` at all.
+    pub gpus: Option<Vec<usize>>,
+    pub layout: GaugeLayout,
+    pub gauges: GaugeSet,
+    pub gauge_labels: GaugeLabels,
+    pub separator: GpuSeparator,
+    /// `--compact` / `c`: one line of bars per GPU instead of the full layout.
+    pub compact: bool,
+    /// Decimal places for temps and power, `0..=MAX_PRECISION`.
+    pub precision: usize,
+    /// `[ui] thousands_separator`, for clocks, VRAM and fan RPM.
+    pub thousands_sep: Option<char>,
+    /// `m`: utilization gauge and sparkline show a moving average over the
+    /// last `smoothing` samples instead of each raw reading.
+    pub smooth_util: bool,
+    pub smoothing: usize,
+    /// `--busy-threshold`: utilization (%) below which `GpuFilter::Busy` hides a GPU.
+    pub busy_threshold: f32,
+    /// First visible row of the selected GPU's process list.
+    pub process_scroll: usize,
+    /// Up/Down/PageUp/PageDown: first visible line of the GPU panels' text
+    /// blocks, once they have more lines than room.
+    pub body_scroll: usize,
+    /// One per entry in `metrics`, same order.
+    pub histories: Vec<GpuHistory>,
+    /// How far back the history widgets reach (`--history`, `[` / `]` at runtime).
+    pub history_window: Duration,
+    /// `--log` destination, written after every successful sample.
+    pub csv_log: Option<CsvLogger>,
+    /// `--record` destination, written after every successful sample.
+    pub recorder: Option<Recorder>,
+    /// Set with `--serve`; refreshed after every successful sample.
+    pub shared: Option<SharedMetrics>,
+    /// `--socket`'s file, removed when the app goes away.
+    pub socket_file: Option<SocketFile>,
+    /// Last sampling error, shown in the footer until a sample succeeds again.
+    pub status: Option<String>,
+    flash: Option<Flash>,
+    pub show_help: bool,
+    /// `[ui] confirm_quit`: `q` / Ctrl-C open `quit_prompt` instead of exiting.
+    pub confirm_quit: bool,
+    /// The "really quit?" prompt is up and takes the next key.
+    pub quit_prompt: bool,
+    pub alert: AlertConfig,
+    /// Per GPU (same order as `metrics`): currently above the alert temp.
+    pub alerting: Vec<bool>,
+    /// Recent alerts and recoveries, oldest first, at most `ALERT_LOG_LEN`.
+    pub alert_log: VecDeque<AlertEvent>,
+    /// `a`: the alert log overlay is up.
+    pub show_alert_log: bool,
+    /// `l`: the color legend overlay is up.
+    pub show_legend: bool,
+    /// A new alert fired this tick; the run loop rings the bell and clears it.
+    pub bell_pending: bool,
+    /// `x` was pressed; the run loop saves the next frame it draws and reports back.
+    pub screenshot_pending: bool,
+    pub redraw: RedrawMode,
+    /// Something on screen is out of date; `RedrawMode::OnChange` draws only then.
+    pub dirty: bool,
+    /// Where each GPU's panel/summary line landed in the last frame, for `--mouse`.
+    /// Filled in by `ui` (which only gets `&App`), hence the `RefCell`.
+    pub gpu_areas: RefCell<Vec<(Rect, usize)>>,
+    /// Lines the tallest panel text block didn't fit in the last frame, and
+    /// how many it did; `body_scroll` is kept within the first.
+    pub body_overflow: Cell<(usize, usize)>,
+    /// Energy drawn by all GPUs since start (or the last `e`), in watt-hours.
+    pub session_energy_wh: f64,
+    /// When the accumulator last advanced; cleared by pausing so the frozen
+    /// stretch isn't billed at whatever the next sample reads.
+    energy_at: Option<Instant>,
+    /// Time between the last few samples' timestamps, i.e. how often the
+    /// backend really got read, next to the `tick_rate` it was asked for.
+    sample_gaps: VecDeque<Duration>,
+    /// Cleared by anything that leaves a gap on purpose (pause, blur, `+`/`-`).
+    last_sample_at: Option<Instant>,
+    /// Metrics the permission hint has already been shown for.
+    hinted: Vec<String>,
+    /// Per GPU (same order as `metrics`): when it stopped showing up in samples.
+    /// Kept with its last readings for `DISCONNECT_GRACE`, then dropped.
+    pub disconnected: Vec<Option<Instant>>,
+}
+
+impl App {
+    pub fn new(
+        backend: Box<dyn GpuBackend>,
+        tick_rate: Duration,
+        history_window: Duration,
+    ) -> io::Result<Self> {
+        Ok(Self {
+            running: true,
+            tick: 0,
+            start: Instant::now(),
+            started_at: SystemTime::now(),
+            samples: 0,
+            samples_since_reset: 0,
+            metrics: vec![],
+            sampler: Sampler::spawn(backend)?,
+            host: hostname(),
+            tick_rate,
+            paused: false,
+            pause_on_blur: false,
+            blurred: false,
+            paused_at: None,
+            temp_unit: TempUnit::Celsius,
+            vram_unit: VramUnit::default(),
+            thresholds: Thresholds::default(),
+            card_health: CardHealth::default(),
+            theme: Theme::default(),
+            theme_name: ThemeName::default(),
+            selected: 0,
+            sort: SortKey::default(),
+            top_n: None,
+            filter: GpuFilter::default(),
+            gpus: None,
+            layout: GaugeLayout::default(),
+            gauge_labels: GaugeLabels::default(),
+            separator: GpuSeparator::default(),
+            gauges: GaugeSet::default(),
+            compact: false,
+            precision: 1,
+            thousands_sep: None,
+            smooth_util: false,
+            smoothing: 5,
+            busy_threshold: 5.0,
+            process_scroll: 0,
+            body_scroll: 0,
+            histories: vec![],
+            history_window,
+            csv_log: None,
+            recorder: None,
+            shared: None,
+            socket_file: None,
+            status: None,
+            flash: None,
+            show_help: false,
+            confirm_quit: false,
+            quit_prompt: false,
+            alert: AlertConfig::default(),
+            alerting: vec![],
+            alert_log: VecDeque::new(),
+            show_alert_log: false,
+            show_legend: false,
+            bell_pending: false,
+            screenshot_pending: false,
+            redraw: RedrawMode::default(),
+            dirty: true,
+            gpu_areas: RefCell::default(),
+            body_overflow: Cell::default(),
+            session_energy_wh: 0.0,
+            energy_at: None,
+            sample_gaps: VecDeque::new(),
+            last_sample_at: None,
+            hinted: vec![],
+            disconnected: vec![],
+        })
+    }
+
+    /// An `App` showing exactly `metrics` (re-stamped on every sample so they
+    /// never go stale), for driving `ui` from tests.
+    #[cfg(test)]
+    pub fn with_metrics(metrics: Vec<GpuMetrics>) -> Self {
+        struct Fixed(Vec<GpuMetrics>);
+        impl GpuBackend for Fixed {
+            fn name(&self) -> &str {
+                "fixed"
+            }
+
+            fn sample(&mut self) -> std::io::Result<Vec<GpuMetrics>> {
+                let mut metrics = self.0.clone();
+                metrics.iter_mut().for_each(|g| g.timestamp = Instant::now());
+                Ok(metrics)
+            }
+        }
+
+        let mut app =
+            Self::new(Box::new(Fixed(metrics)), Duration::from_millis(500), Duration::from_secs(60)).unwrap();
+        app.on_tick();
+        app
+    }
+
+    pub fn on_tick(&mut self) {
+        if self.flash.as_ref().is_some_and(|f| f.at.elapsed() >= f.duration) {
+            self.flash = None;
+            self.dirty = true;
+        }
+        if self.paused {
+            return;
+        }
+
+        // Nothing yet on screen: worth waiting for. After that, a backend still
+        // busy past the wait just leaves the last readings up (and going stale).
+        let wait = if self.metrics.is_empty() { FIRST_SAMPLE_WAIT } else { self.tick_rate.min(MAX_SAMPLE_WAIT) };
+        // On error keep the last good metrics on screen and just report it.
+        match self.sampler.poll(wait) {
+            // The stale marker and its age have to show up without a new sample
+            None => self.dirty |= self.stale_for().is_some(),
+            Some(Ok(metrics)) => {
+                self.dirty |= self.status.take().is_some();
+                self.samples += 1;
+                self.samples_since_reset += 1;
+                if let Some(gpu) = metrics.first() {
+                    self.note_sample_time(gpu.timestamp);
+                }
+                // Outputs only ever see what the backend actually reported
+                if let Some(log) = &mut self.csv_log
+                    && let Err(e) = log.log(&metrics)
+                {
+                    self.status = Some(format!("CSV log write failed: {e}"));
+                }
+                if let Some(rec) = &mut self.recorder
+                    && let Err(e) = rec.record(&metrics)
+                {
+                    self.status = Some(format!("recording write failed: {e}"));
+                }
+                if let Some(shared) = &self.shared
+                    && let Ok(mut latest) = shared.lock()
+                {
+                    latest.clone_from(&metrics);
+                }
+                let metrics = self.only_chosen_gpus(metrics);
+                // Before the first sample there's a "waiting" screen to replace
+                self.dirty |= metrics != self.metrics || self.samples == 1;
+                self.reconcile(metrics);
+                self.keep_selection_shown();
+                self.clamp_process_scroll();
+                self.push_history();
+                self.accumulate_energy();
+                self.check_alerts();
+                self.hint_unreadable();
+            }
+            Some(Err(e)) => {
+                self.status = Some(format!("sample failed: {e}"));
+                self.dirty = true;
+            }
+        }
+        // A failing source's time since its last success counts up on screen
+        self.dirty |= self.backend_health().iter().any(|h| !h.ok);
+        self.tick += 1;
+    }
+
+    /// Drops the GPUs `--gpus` didn't ask for. Indices the first sample doesn't
+    /// have are warned about and forgotten, so `device_index` stays right.
+    fn only_chosen_gpus(&mut self, metrics: Vec<GpuMetrics>) -> Vec<GpuMetrics> {
+        let Some(ids) = self.gpus.take() else {
+            return metrics;
+        };
+        let (ids, missing): (Vec<usize>, Vec<usize>) = if self.metrics.is_empty() {
+            ids.into_iter().partition(|&i| i < metrics.len())
+        } else {
+            (ids, vec![])
+        };
+        if !missing.is_empty() {
+            let missing: Vec<String> = missing.iter().map(usize::to_string).collect();
+            self.flash(format!("--gpus: no GPU {} (found {})", missing.join(", "), metrics.len()), true);
+        }
+        let kept = metrics.into_iter().enumerate().filter(|(i, _)| ids.contains(i)).map(|(_, gpu)| gpu).collect();
+        self.gpus = Some(ids);
+        kept
+    }
+
+    /// The number to show for `metrics[idx]`: its backend index, which only
+    /// differs from `idx` under `--gpus`.
+    pub fn device_index(&self, idx: usize) -> usize {
+        self.gpus.as_ref().and_then(|ids| ids.get(idx).copied()).unwrap_or(idx)
+    }
+
+    /// Replaces `metrics` with a fresh sample, matching GPUs up with the previous
+    /// one so history, alert state and the selection follow the card rather than
+    /// its position. GPUs that vanished keep their old slot, marked disconnected,
+    /// until `DISCONNECT_GRACE` runs out.
+    fn reconcile(&mut self, fresh: Vec<GpuMetrics>) {
+        let now = Instant::now();
+        let old_keys = gpu_keys(&self.metrics);
+        let selected_key = old_keys.get(self.selected).cloned();
+        let mut keys = gpu_keys(&fresh);
+
+        let mut old_metrics: Vec<Option<GpuMetrics>> = self.metrics.drain(..).map(Some).collect();
+        let mut old_histories: Vec<Option<GpuHistory>> = self.histories.drain(..).map(Some).collect();
+        let old_alerting = std::mem::take(&mut self.alerting);
+        let old_disconnected = std::mem::take(&mut self.disconnected);
+
+        for (gpu, key) in fresh.into_iter().zip(&keys) {
+            let prev = old_keys.iter().position(|k| k == key);
+            if let Some(i) = prev {
+                old_metrics[i] = None;
+            }
+            let history = prev.and_then(|i| old_histories.get_mut(i)?.take());
+            self.histories.push(history.unwrap_or_default());
+            self.alerting.push(prev.is_some_and(|i| old_alerting.get(i) == Some(&true)));
+            // A composite backend keeps a failed backend's cards in place itself
+            let since = prev.and_then(|i| old_disconnected.get(i).copied().flatten()).unwrap_or(now);
+            self.disconnected.push(gpu.disconnected.then_some(since));
+            self.metrics.push(gpu);
+        }
+
+        // Whatever wasn't matched has gone missing; put it back where it was
+        for (i, gpu) in old_metrics.into_iter().enumerate() {
+            let Some(gpu) = gpu else { continue };
+            let since = old_disconnected.get(i).copied().flatten().unwrap_or(now);
+            if now.duration_since(since) >= DISCONNECT_GRACE {
+                continue;
+            }
+            let at = i.min(self.metrics.len());
+            let history = old_histories.get_mut(i).and_then(Option::take);
+            self.histories.insert(at, history.unwrap_or_default());
+            self.alerting.insert(at, false);
+            self.disconnected.insert(at, Some(since));
+            self.metrics.insert(at, gpu);
+            keys.insert(at, old_keys[i].clone());
+        }
+
+        let still_there = selected_key.and_then(|k| keys.iter().position(|key| *key == k));
+        self.selected = still_there.unwrap_or(self.selected).min(self.metrics.len().saturating_sub(1));
+    }
+
+    /// Age of the newest reading, once it's more than two intervals old (a hung
+    /// or failing backend). Never while paused: frozen data is the point there.
+    pub fn stale_for(&self) -> Option<Duration> {
+        if self.paused {
+            return None;
+        }
+        let newest = self
+            .metrics
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| !self.is_disconnected(i))
+            .map(|(_, gpu)| gpu.timestamp)
+            .max()?;
+        let age = newest.elapsed();
+        (age > self.tick_rate * STALE_AFTER_INTERVALS).then_some(age)
+    }
+
+    /// The settings that are written back to `[ui]` if they change while running.
+    pub fn preferences(&self) -> Preferences {
+        Preferences {
+            temp_unit: self.temp_unit,
+            theme: self.theme_name,
+            layout: self.layout,
+            gauge_labels: self.gauge_labels,
+            separator: self.separator,
+            gauges: self.gauges,
+            interval_ms: self.tick_rate.as_millis() as u64,
+        }
+    }
+
+    /// Where the numbers come from, e.g. "AMD sysfs".
+    pub fn source(&self) -> &str {
+        self.sampler.name()
+    }
+
+    pub fn is_mock(&self) -> bool {
+        self.sampler.is_mock()
+    }
+
+    /// Per-source status when several backends are composed; empty otherwise.
+    pub fn backend_health(&self) -> &[BackendHealth] {
+        self.sampler.health()
+    }
+
+    pub fn is_disconnected(&self, idx: usize) -> bool {
+        self.disconnected.get(idx).is_some_and(|d| d.is_some())
+    }
+
+    /// Samples that cover `history_window` at the current interval.
+    pub fn history_len(&self) -> usize {
+        let samples = self.history_window.as_secs_f64() / self.tick_rate.as_secs_f64();
+        (samples.ceil() as usize).max(1)
+    }
+
+    /// Total power of the connected GPUs times the time since the previous
+    /// sample. An interval where no GPU reports power adds nothing.
+    fn accumulate_energy(&mut self) {
+        let now = Instant::now();
+        let watts: Option<f32> = self
+            .metrics
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| !self.is_disconnected(i))
+            .filter_map(|(_, gpu)| gpu.power_w)
+            .reduce(|a, b| a + b);
+        if let (Some(watts), Some(prev)) = (watts, self.energy_at) {
+            self.session_energy_wh += watts as f64 * now.duration_since(prev).as_secs_f64() / 3600.0;
+        }
+        self.energy_at = Some(now);
+    }
+
+    fn note_sample_time(&mut self, at: Instant) {
+        if let Some(prev) = self.last_sample_at.replace(at) {
+            self.sample_gaps.push_back(at.saturating_duration_since(prev));
+            trim_front(&mut self.sample_gaps, SAMPLE_GAP_WINDOW);
+        }
+    }
+
+    /// The backend has answered and there's nothing to show (not even a
+    /// disconnected card), as opposed to still waiting on the first sample.
+    pub fn no_gpus(&self) -> bool {
+        self.samples > 0 && self.metrics.is_empty()
+    }
+
+    /// The measured sampling interval, once there are two samples to go by.
+    pub fn actual_interval(&self) -> Option<Duration> {
+        let n = self.sample_gaps.len() as u32;
+        (n > 0).then(|| self.sample_gaps.iter().sum::<Duration>() / n)
+    }
+
+    /// Start measuring afresh, e.g. after a pause that isn't sampling drift.
+    fn reset_sample_gaps(&mut self) {
+        self.sample_gaps.clear();
+        self.last_sample_at = None;
+    }
+
+    fn push_history(&mut self) {
+        let cap = self.history_len();
+        for (i, (history, gpu)) in self.histories.iter_mut().zip(&self.metrics).enumerate() {
+            if self.disconnected[i].is_none() {
+                history.push(gpu, self.history_window, cap);
+            }
+        }
+    }
+
+    /// `[` / `]`: halve or double the history window, within sane bounds.
+    /// Shrinking drops the oldest samples right away; growing just lets the
+    /// buffers fill further from here on.
+    fn resize_history(&mut self, grow: bool) {
+        let secs = self.history_window.as_secs();
+        let secs = if grow { secs * 2 } else { secs / 2 };
+        self.history_window = Duration::from_secs(secs.clamp(MIN_HISTORY_SECS, MAX_HISTORY_SECS));
+        self.trim_histories();
+        self.flash(format!("history window: {}s", self.history_window.as_secs()), false);
+    }
+
+    /// `n` / `N`: one GPU fewer / more in the top N. Starting from everything
+    /// shown; growing back to everything turns it off again.
+    fn change_top_n(&mut self, fewer: bool) {
+        let total = self.ranked().len();
+        let current = self.top_n.unwrap_or(total);
+        let n = if fewer { current.saturating_sub(1).max(1) } else { current + 1 };
+        if n >= total {
+            self.top_n = None;
+            self.flash("showing all GPUs".into(), false);
+            return;
+        }
+        self.top_n = Some(n);
+        if self.sort == SortKey::Device {
+            self.sort = SortKey::Temperature;
+        }
+        self.flash(format!("top {n} by {}", self.sort.label()), false);
+        self.keep_selection_shown();
+    }
+
+    /// `r`: a fresh measurement window without restarting. Histories, power
+    /// stats, energy, the elapsed clock and sample count all start over;
+    /// the alert log and whether a card is mid-alert don't.
+    fn reset_measurements(&mut self) {
+        for h in &mut self.histories {
+            *h = GpuHistory { over_power: h.over_power, ..Default::default() };
+        }
+        self.session_energy_wh = 0.0;
+        self.energy_at = None;
+        self.start = Instant::now();
+        // Not `samples`: that also says whether the first sample is in yet
+        self.samples_since_reset = 0;
+        self.reset_sample_gaps();
+        self.flash("history, averages, peaks and energy reset".into(), false);
+    }
+
+    /// `+` / `-`: halve or double the sampling interval. The history window
+    /// stays the same length of time, so slowing down keeps fewer samples.
+    fn change_interval(&mut self, faster: bool) {
+        let ms = self.tick_rate.as_millis() as u64;
+        let ms = if faster { ms / 2 } else { ms * 2 };
+        self.tick_rate = Duration::from_millis(ms.clamp(MIN_INTERVAL_MS, MAX_INTERVAL_MS));
+        self.reset_sample_gaps();
+        self.trim_histories();
+        self.flash(format!("interval: {}ms", self.tick_rate.as_millis()), false);
+    }
+
+    /// Drops whatever no longer fits `history_len` / `history_window`.
+    fn trim_histories(&mut self) {
+        let cap = self.history_len();
+        let cutoff = Instant::now().checked_sub(self.history_window);
+        for h in &mut self.histories {
+            trim_front(&mut h.util, cap);
+            trim_front(&mut h.power, cap);
+            trim_front(&mut h.vram, cap);
+            trim_front(&mut h.core_clock, cap);
+            trim_front(&mut h.mem_clock, cap);
+            if let Some(cutoff) = cutoff {
+                h.temp.retain(|&(t, _)| t >= cutoff);
+            }
+        }
+    }
+
+    /// Fires once on the way up through the alert temp, then stays latched until
+    /// the temp drops `hysteresis_c` below it, so a card hovering at the limit
+    /// doesn't re-alert every tick.
+    ///
+    /// Power going critical (and back under the warn level) isn't an alert,
+    /// but goes in the alert log alongside them.
+    fn check_alerts(&mut self) {
+        let trip = self.alert.temp_c.unwrap_or(self.thresholds.temp.crit);
+        let clear = trip - self.alert.hysteresis_c;
+        let power = self.thresholds.power;
+        let (unit, precision) = (self.temp_unit, self.precision);
+        let temp = |t: f32| format!("{}{}", fmt_dec(Some(unit.convert(t)), precision), unit.suffix());
+        let mut events = vec![];
+        for (i, (alerting, gpu)) in self.alerting.iter_mut().zip(&self.metrics).enumerate() {
+            if self.disconnected[i].is_some() {
+                continue;
+            }
+            match gpu.temperature_c {
+                Some(t) if !*alerting && t >= trip => {
+                    *alerting = true;
+                    self.bell_pending |= self.alert.bell;
+                    events.push((i, format!("temp {} critical", temp(t)), true));
+                }
+                Some(t) if *alerting && t < clear => {
+                    *alerting = false;
+                    events.push((i, format!("temp recovered ({})", temp(t)), false));
+                }
+                _ => {}
+            }
+            let over = &mut self.histories[i].over_power;
+            match gpu.power_w {
+                Some(w) if !*over && w >= power.crit => {
+                    *over = true;
+                    events.push((i, format!("power {w:.0} W critical"), true));
+                }
+                Some(w) if *over && w < power.warn => {
+                    *over = false;
+                    events.push((i, format!("power recovered ({w:.0} W)"), false));
+                }
+                _ => {}
+            }
+        }
+        for (i, text, critical) in events {
+            self.log_alert(format!("GPU {} {text}", self.device_index(i)), critical);
+        }
+    }
+
+    fn log_alert(&mut self, text: String, critical: bool) {
+        let at = self.started_at + self.start.elapsed();
+        self.alert_log.push_back(AlertEvent { at, text, critical });
+        trim_front(&mut self.alert_log, ALERT_LOG_LEN);
+    }
+
+    /// One footer hint per metric the backend wasn't allowed to read, instead
+    /// of leaving it at "--" with no explanation.
+    fn hint_unreadable(&mut self) {
+        let mut new: Vec<String> = vec![];
+        for name in self.metrics.iter().flat_map(|gpu| &gpu.unreadable) {
+            if !self.hinted.contains(name) && !new.contains(name) {
+                new.push(name.clone());
+            }
+        }
+        if new.is_empty() {
+            return;
+        }
+        let text = format!("{} unreadable: run as root or add your user to the video group", new.join(", "));
+        self.hinted.extend(new);
+        self.flash = Some(Flash { text, error: true, at: Instant::now(), duration: HINT_DURATION });
+    }
+
+    /// Utilization as the gauge should show it: raw, or smoothed after `m`.
+    /// Still `None` when the latest sample has no reading, smoothed or not.
+    pub fn shown_util(&self, idx: usize) -> Option<f32> {
+        let raw = self.metrics.get(idx)?.utilization_pct?;
+        if !self.smooth_util {
+            return Some(raw);
+        }
+        self.histories.get(idx).and_then(|h| h.util_mean(self.smoothing)).or(Some(raw))
+    }
+
+    fn toggle_smoothing(&mut self) {
+        self.smooth_util = !self.smooth_util;
+        let text = if self.smooth_util {
+            format!("utilization: average of last {} samples", self.smoothing)
+        } else {
+            "utilization: raw".into()
+        };
+        self.flash(text, false);
+    }
+
+    pub fn selected_history(&self) -> Option<&GpuHistory> {
+        self.histories.get(self.selected)
+    }
+
+    /// Indices into `metrics` in the order the UI draws them, after `filter`,
+    /// `sort` and `top_n`.
+    pub fn display_order(&self) -> Vec<usize> {
+        let mut order = self.ranked();
+        order.truncate(self.top_n.unwrap_or(usize::MAX));
+        order
+    }
+
+    /// The GPUs `top_n` leaves out, best first.
+    pub fn collapsed(&self) -> Vec<usize> {
+        let top = self.top_n.unwrap_or(usize::MAX);
+        self.ranked().into_iter().skip(top).collect()
+    }
+
+    fn ranked(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.metrics.len()).filter(|&i| self.is_shown(i)).collect();
+        if self.sort != SortKey::Device {
+            // Stable, so ties (and the unknowns at the end) keep device order
+            order.sort_by(|&a, &b| {
+                let (a, b) = (self.sort.value(&self.metrics[a]), self.sort.value(&self.metrics[b]));
+                match (a, b) {
+                    (Some(a), Some(b)) => b.total_cmp(&a),
+                    (a, b) => b.is_some().cmp(&a.is_some()),
+                }
+            });
+        }
+        order
+    }
+
+    /// Disconnected GPUs stay visible under `Busy` so the user sees them go.
+    fn is_shown(&self, idx: usize) -> bool {
+        match self.filter {
+            GpuFilter::All => true,
+            GpuFilter::Busy => {
+                self.is_disconnected(idx)
+                    || self.metrics[idx].utilization_pct.is_none_or(|u| u >= self.busy_threshold)
+            }
+        }
+    }
+
+    /// The selection can't sit on a GPU the filter hides; move it to the first shown one.
+    fn keep_selection_shown(&mut self) {
+        let order = self.display_order();
+        if !order.contains(&self.selected)
+            && let Some(&first) = order.first()
+        {
+            self.selected = first;
+            self.process_scroll = 0;
+        }
+    }
+
+    /// Step through `display_order`, so Left/Right follow what's on screen.
+    fn select_step(&mut self, forward: bool) {
+        let order = self.display_order();
+        if order.is_empty() {
+            return;
+        }
+        let next = match order.iter().position(|&i| i == self.selected) {
+            Some(pos) if forward => order[(pos + 1) % order.len()],
+            Some(pos) => order[(pos + order.len() - 1) % order.len()],
+            None => order[0],
+        };
+        self.selected = next;
+        self.process_scroll = 0;
+    }
+
+    fn select_next(&mut self) {
+        self.select_step(true);
+    }
+
+    fn select_prev(&mut self) {
+        self.select_step(false);
+    }
+
+    fn toggle_gauge(&mut self, kind: GaugeKind) {
+        self.gauges.toggle(kind);
+        let state = if self.gauges.contains(kind) { "shown" } else { "hidden" };
+        self.flash(format!("{} gauge {state}", kind.label()), false);
+    }
+
+    fn flash(&mut self, text: String, error: bool) {
+        self.dirty = true;
+        self.flash = Some(Flash { text, error, at: Instant::now(), duration: FLASH_DURATION });
+    }
+
+    /// For `--no-tui`, which has no footer to show flashes in.
+    pub fn take_flash(&mut self) -> Option<Flash> {
+        self.flash.take()
+    }
+
+    pub fn active_flash(&self) -> Option<&Flash> {
+        self.flash.as_ref().filter(|f| f.at.elapsed() < f.duration)
+    }
+
+    fn snapshot(&mut self) {
+        match write_snapshot(&self.metrics) {
+            Ok(path) => self.flash(format!("snapshot written to {}", path.display()), false),
+            Err(e) => self.flash(format!("snapshot failed: {e}"), true),
+        }
+    }
+
+    /// Called by the run loop with whatever `write_screenshot` did.
+    pub fn screenshot_taken(&mut self, result: io::Result<PathBuf>) {
+        match result {
+            Ok(stem) => self.flash(format!("screenshot written to {}.{{ans,svg}}", stem.display()), false),
+            Err(e) => self.flash(format!("screenshot failed: {e}"), true),
+        }
+    }
+
+    fn scroll_processes(&mut self, down: bool) {
+        if down {
+            self.process_scroll += 1;
+            self.clamp_process_scroll();
+        } else {
+            self.process_scroll = self.process_scroll.saturating_sub(1);
+        }
+    }
+
+    /// `lines` down (or up), never past the last line of the tallest text block.
+    fn scroll_body(&mut self, lines: isize) {
+        let (overflow, _) = self.body_overflow.get();
+        self.body_scroll = self.body_scroll.saturating_add_signed(lines).min(overflow);
+    }
+
+    /// PageUp/PageDown move by what's visible, less a line of context.
+    fn body_page(&self) -> isize {
+        self.body_overflow.get().1.saturating_sub(1).max(1) as isize
+    }
+
+    fn clamp_process_scroll(&mut self) {
+        let n = self.metrics.get(self.selected).map_or(0, |g| g.processes.len());
+        self.process_scroll = self.process_scroll.min(n.saturating_sub(1));
+    }
+
+    /// `q` / Ctrl-C: close an overlay if one is up, otherwise quit (or ask first).
+    fn request_quit(&mut self) {
+        if self.show_help || self.show_alert_log || self.show_legend {
+            self.show_help = false;
+            self.show_alert_log = false;
+            self.show_legend = false;
+        } else if self.confirm_quit {
+            self.quit_prompt = true;
+        } else {
+            self.running = false;
+        }
+    }
+
+    /// One terminal event, as read by the run loop. Only the ones that change
+    /// something (a bound key, a click that lands, a resize) ask for a
+    /// redraw, so `--redraw on-change` stays idle under stray input.
+    pub fn on_event(&mut self, event: Event) {
+        match event {
+            Event::Key(key) if key.kind == KeyEventKind::Press => self.on_key(key),
+            Event::Mouse(mouse) => self.on_mouse(mouse),
+            Event::FocusLost => self.on_focus(false),
+            Event::FocusGained => self.on_focus(true),
+            Event::Resize(..) => self.dirty = true,
+            _ => {}
+        }
+    }
+
+    /// Presses `keys` in order the way the run loop would, stopping at the
+    /// first one that ends it; returns whether it would keep running.
+    pub fn on_keys(&mut self, keys: impl IntoIterator<Item = KeyCode>) -> bool {
+        for code in keys {
+            if !self.running {
+                break;
+            }
+            self.on_event(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)));
+        }
+        self.running
+    }
+
+    pub fn on_key(&mut self, key: KeyEvent) {
+        let ctrl_c = key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+        if self.quit_prompt {
+            // Repeating the quit key confirms too; anything else backs out
+            self.quit_prompt = false;
+            let confirm = matches!(key.code, KeyCode::Char('y' | 'q') | KeyCode::Enter | KeyCode::Esc);
+            self.running = !(confirm || ctrl_c);
+            self.dirty = true;
+            return;
+        }
+        if ctrl_c {
+            self.request_quit();
+            self.dirty = true;
+            return;
+        }
+        match key.code {
+            KeyCode::Esc => self.running = false,
+            KeyCode::Char('q') => self.request_quit(),
+            KeyCode::Char(' ') => {
+                self.paused = !self.paused;
+                self.paused_at = self.paused.then(Instant::now);
+                self.energy_at = None;
+                self.reset_sample_gaps();
+            }
+            KeyCode::Char('e') => {
+                self.session_energy_wh = 0.0;
+                self.flash("energy counter reset".into(), false);
+            }
+            KeyCode::Char('r') => self.reset_measurements(),
+            KeyCode::Char('f') => self.temp_unit = self.temp_unit.toggled(),
+            KeyCode::Right | KeyCode::Tab => self.select_next(),
+            KeyCode::Left | KeyCode::BackTab => self.select_prev(),
+            KeyCode::Char('c') => self.compact = !self.compact,
+            KeyCode::Char('g') => {
+                self.gauge_labels = self.gauge_labels.next();
+                self.flash(format!("gauge readings: {}", self.gauge_labels.label()), false);
+            }
+            KeyCode::Char('b') => {
+                self.separator = self.separator.next();
+                self.flash(format!("GPU separator: {}", self.separator.label()), false);
+            }
+            KeyCode::Char('d') => self.precision = (self.precision + 1) % (MAX_PRECISION + 1),
+            KeyCode::Char('o') => {
+                self.sort = self.sort.next();
+                // Device order has no "top"
+                if self.top_n.is_some() && self.sort == SortKey::Device {
+                    self.sort = self.sort.next();
+                }
+            }
+            KeyCode::Char('n') => self.change_top_n(true),
+            KeyCode::Char('N') => self.change_top_n(false),
+            KeyCode::Char(c @ '1'..='5') => self.toggle_gauge(GaugeKind::ALL[c as usize - '1' as usize]),
+            KeyCode::Up => self.scroll_body(-1),
+            KeyCode::Down => self.scroll_body(1),
+            KeyCode::PageUp => self.scroll_body(-self.body_page()),
+            KeyCode::PageDown => self.scroll_body(self.body_page()),
+            KeyCode::Char('j') => self.scroll_processes(true),
+            KeyCode::Char('k') => self.scroll_processes(false),
+            KeyCode::Char('s') => self.snapshot(),
+            KeyCode::Char('x') => self.screenshot_pending = true,
+            KeyCode::Char('a') => self.show_alert_log = !self.show_alert_log,
+            KeyCode::Char('l') => self.show_legend = !self.show_legend,
+            KeyCode::Char('m') => self.toggle_smoothing(),
+            KeyCode::Char('?') => self.show_help = !self.show_help,
+            KeyCode::Char('[') => self.resize_history(false),
+            KeyCode::Char(']') => self.resize_history(true),
+            // `=` is `+` without shift on most layouts
+            KeyCode::Char('+' | '=') => self.change_interval(true),
+            KeyCode::Char('-') => self.change_interval(false),
+            _ => return,
+        }
+        self.dirty = true;
+    }
+
+    /// Focus-in/out from the terminal; only does anything with `--pause-on-blur`.
+    pub fn on_focus(&mut self, focused: bool) {
+        let blurred = !focused;
+        if !self.pause_on_blur || self.blurred == blurred {
+            return;
+        }
+        self.blurred = blurred;
+        self.dirty = true;
+        // Energy needs two samples a known interval apart; the gap isn't one
+        self.energy_at = None;
+        self.reset_sample_gaps();
+        if focused {
+            // Straight to a fresh sample rather than showing how things were
+            self.on_tick();
+        }
+    }
+
+    /// Left click selects the GPU under the pointer; the wheel scrolls the process list.
+    pub fn on_mouse(&mut self, mouse: MouseEvent) {
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                let hit = self.gpu_areas.borrow().iter().find_map(|&(area, idx)| {
+                    let inside = mouse.column >= area.x
+                        && mouse.column < area.right()
+                        && mouse.row >= area.y
+                        && mouse.row < area.bottom();
+                    inside.then_some(idx)
+                });
+                if let Some(idx) = hit.filter(|&i| i != self.selected) {
+                    self.selected = idx;
+                    self.process_scroll = 0;
+                    self.dirty = true;
+                }
+            }
+            MouseEventKind::ScrollDown | MouseEventKind::ScrollUp => {
+                self.scroll_processes(mouse.kind == MouseEventKind::ScrollDown);
+                self.dirty = true;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+
+    /// Hands out a fixed list of GPU names per sample, repeating the last one.
+    struct Scripted(Vec<Vec<&'static str>>);
+
+    impl GpuBackend for Scripted {
+        fn name(&self) -> &str {
+            "scripted"
+        }
+
+        fn sample(&mut self) -> io::Result<Vec<GpuMetrics>> {
+            let names = if self.0.len() > 1 { self.0.remove(0) } else { self.0[0].clone() };
+            Ok(names
+                .into_iter()
+                .map(|name| GpuMetrics { name: name.into(), ..Default::default() })
+                .collect())
+        }
+    }
+
+    fn app(script: Vec<Vec<&'static str>>) -> App {
+        App::new(Box::new(Scripted(script)), Duration::from_millis(500), Duration::from_secs(60)).unwrap()
+    }
+
+    #[test]
+    fn vanished_gpu_stays_in_place_marked_disconnected() {
+        let mut app = app(vec![vec!["a", "b", "c"], vec!["a", "c"]]);
+        app.on_tick();
+        app.selected = 2;
+        app.on_tick();
+
+        let names: Vec<&str> = app.metrics.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, ["a", "b", "c"]);
+        assert!(app.is_disconnected(1));
+        assert!(!app.is_disconnected(2));
+        // Selection follows "c", and every per-GPU vec stays the same length
+        assert_eq!(app.selected, 2);
+        assert_eq!(app.histories.len(), 3);
+        assert_eq!(app.alerting.len(), 3);
+    }
+
+    #[test]
+    fn gpus_flag_keeps_backend_indices_and_drops_missing_ones() {
+        let mut app = app(vec![vec!["a", "b", "c"]]);
+        app.gpus = Some(vec![1, 2, 7]);
+        app.on_tick();
+        app.on_tick();
+
+        let names: Vec<&str> = app.metrics.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, ["b", "c"]);
+        assert_eq!((app.device_index(0), app.device_index(1)), (1, 2));
+        assert!(app.active_flash().is_some_and(|f| f.error && f.text.contains('7')));
+    }
+
+    #[test]
+    fn permission_hint_names_the_metric_once() {
+        let gpu = GpuMetrics { unreadable: vec!["power".into()], ..Default::default() };
+        let mut app = App::with_metrics(vec![gpu]);
+        let flash = app.take_flash().unwrap();
+        assert!(flash.error && flash.text.starts_with("power unreadable"), "{}", flash.text);
+        app.on_tick();
+        assert!(app.take_flash().is_none());
+    }
+
+    #[test]
+    fn q_closes_help_before_quitting_and_can_ask_first() {
+        let press = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+        let mut app = app(vec![vec!["a"]]);
+        app.show_help = true;
+        app.on_key(press('q'));
+        assert!(app.running && !app.show_help);
+
+        app.confirm_quit = true;
+        app.on_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+        assert!(app.running && app.quit_prompt);
+        app.on_key(press('n'));
+        assert!(app.running && !app.quit_prompt);
+        app.on_key(press('q'));
+        app.on_key(press('y'));
+        assert!(!app.running);
+    }
+
+    #[test]
+    fn q_and_esc_end_the_run_loop_and_other_keys_dont() {
+        assert!(!app(vec![vec!["a"]]).on_keys([KeyCode::Char('q')]));
+        assert!(!app(vec![vec!["a"]]).on_keys([KeyCode::Esc]));
+
+        let mut app = app(vec![vec!["a"]]);
+        let others = [KeyCode::Char('f'), KeyCode::Char('o'), KeyCode::Tab, KeyCode::Down, KeyCode::Char('?')];
+        assert!(app.on_keys(others));
+        // `?` opened help, so the first `q` only closes it
+        assert!(app.on_keys([KeyCode::Char('q')]));
+        assert!(!app.on_keys([KeyCode::Char('q'), KeyCode::Char('f')]));
+        assert_eq!(app.temp_unit, TempUnit::Fahrenheit);
+    }
+
+    #[test]
+    fn only_changed_samples_and_input_ask_for_a_redraw() {
+        let mut app = app(vec![vec!["a", "b"], vec!["a", "b"], vec!["a"]]);
+        app.on_tick();
+        assert!(std::mem::take(&mut app.dirty));
+        // Same readings, new timestamps
+        app.on_tick();
+        assert!(!app.dirty);
+        app.on_event(Event::Resize(80, 24));
+        assert!(std::mem::take(&mut app.dirty));
+        app.on_tick();
+        assert!(app.dirty && app.is_disconnected(1));
+    }
+
+    #[test]
+    fn ignored_input_leaves_the_screen_alone() {
+        let mut app = app(vec![vec!["a"]]);
+        app.on_tick();
+        app.dirty = false;
+        app.on_keys([KeyCode::Char('z'), KeyCode::F(5)]);
+        let motion = MouseEvent { kind: MouseEventKind::Moved, column: 3, row: 4, modifiers: KeyModifiers::NONE };
+        app.on_event(Event::Mouse(motion));
+        app.on_event(Event::FocusLost);
+        assert!(!app.dirty);
+        app.on_keys([KeyCode::Char('f')]);
+        assert!(app.dirty);
+    }
+
+    #[test]
+    fn interval_keys_stay_in_bounds() {
+        let press = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+        let mut app = app(vec![vec!["a"]]);
+        assert_eq!(app.tick_rate, Duration::from_millis(500));
+
+        app.on_key(press('+'));
+        assert_eq!(app.tick_rate, Duration::from_millis(250));
+        (0..5).for_each(|_| app.on_key(press('=')));
+        assert_eq!(app.tick_rate, Duration::from_millis(MIN_INTERVAL_MS));
+
+        // 60s window at 10s per sample: six samples
+        (0..10).for_each(|_| app.on_key(press('-')));
+        assert_eq!(app.tick_rate, Duration::from_millis(MAX_INTERVAL_MS));
+        assert_eq!(app.history_len(), 6);
+    }
+
+    #[test]
+    fn reset_key_starts_a_fresh_measurement_window() {
+        let mut app = app(vec![vec!["a"]]);
+        (0..3).for_each(|_| app.on_tick());
+        let samples = app.samples;
+        app.session_energy_wh = 4.2;
+        app.histories[0].power.extend([90.0, 110.0]);
+        app.histories[0].power_peak = Some(110.0);
+        app.histories[0].over_power = true;
+
+        app.on_key(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE));
+        let h = &app.histories[0];
+        assert!(h.power.is_empty() && h.power_peak.is_none() && h.power_avg().is_none());
+        assert!(h.over_power, "still mid-alert, so recovery gets logged");
+        assert_eq!((app.session_energy_wh, app.samples_since_reset), (0.0, 0));
+        assert_eq!(app.samples, samples);
+        assert!(app.active_flash().is_some_and(|f| f.text.contains("reset")));
+    }
+
+    #[test]
+    fn measured_interval_averages_sample_gaps_and_restarts_after_a_pause() {
+        let mut app = app(vec![vec!["a"]]);
+        app.reset_sample_gaps();
+        let t0 = Instant::now();
+        for ms in [0, 500, 1020, 1530] {
+            app.note_sample_time(t0 + Duration::from_millis(ms));
+        }
+        assert_eq!(app.actual_interval(), Some(Duration::from_millis(510)));
+
+        app.on_key(KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE));
+        assert_eq!(app.actual_interval(), None);
+        app.note_sample_time(t0 + Duration::from_secs(60));
+        assert_eq!(app.actual_interval(), None);
+    }
+
+    #[test]
+    fn selection_is_clamped_when_gpus_disappear_for_good() {
+        let mut app = app(vec![vec!["a", "b"], vec!["a"]]);
+        app.on_tick();
+        app.selected = 1;
+        app.on_tick();
+        // Pretend the grace period ran out
+        app.disconnected[1] = Some(Instant::now() - DISCONNECT_GRACE);
+        app.on_tick();
+
+        assert_eq!(app.metrics.len(), 1);
+        assert_eq!(app.selected, 0);
+        app.select_next();
+        assert_eq!(app.selected, 0);
+    }
+
+    #[test]
+    fn smoothing_averages_recent_samples_but_keeps_raw_around() {
+        let mut app = app(vec![vec!["a"]]);
+        app.on_tick();
+        app.histories[0].util = [0.0, 100.0, 20.0, 40.0].into();
+        app.metrics[0].utilization_pct = Some(40.0);
+        app.smoothing = 3;
+        assert_eq!(app.shown_util(0), Some(40.0));
+
+        app.on_key(KeyEvent::from(KeyCode::Char('m')));
+        assert_eq!(app.shown_util(0), Some(160.0 / 3.0));
+        assert_eq!(app.histories[0].util_smoothed(3), [0.0, 50.0, 40.0, 160.0 / 3.0]);
+        assert_eq!(app.metrics[0].utilization_pct, Some(40.0));
+    }
+
+    #[test]
+    fn alert_log_records_crossings_and_recoveries_once() {
+        let mut app = app(vec![vec!["a"]]);
+        app.on_tick();
+        for (temp, power) in [(95.0, 310.0), (96.0, 305.0), (60.0, 250.0), (60.0, 150.0)] {
+            app.metrics[0].temperature_c = Some(temp);
+            app.metrics[0].power_w = Some(power);
+            app.check_alerts();
+        }
+        let log: Vec<(&str, bool)> = app.alert_log.iter().map(|e| (e.text.as_str(), e.critical)).collect();
+        assert_eq!(
+            log,
+            [
+                ("GPU 0 temp 95.0°C critical", true),
+                ("GPU 0 power 310 W critical", true),
+                ("GPU 0 temp recovered (60.0°C)", false),
+                ("GPU 0 power recovered (150 W)", false),
+            ]
+        );
+
+        // Same precision as the panels
+        app.precision = 0;
+        app.metrics[0].temperature_c = Some(95.4);
+        app.check_alerts();
+        assert_eq!(app.alert_log.back().unwrap().text, "GPU 0 temp 95°C critical");
+    }
+
+    #[test]
+    fn top_n_keeps_the_hottest_and_collapses_the_rest() {
+        let mut app = app(vec![vec!["a", "b", "c", "d"]]);
+        app.on_tick();
+        for (gpu, temp) in app.metrics.iter_mut().zip([60.0, 85.0, 70.0, 90.0]) {
+            gpu.temperature_c = Some(temp);
+        }
+
+        let key = |c| KeyEvent::from(KeyCode::Char(c));
+        app.on_key(key('n'));
+        app.on_key(key('n'));
+        assert_eq!((app.top_n, app.sort), (Some(2), SortKey::Temperature));
+        assert_eq!(app.display_order(), [3, 1]);
+        assert_eq!(app.collapsed(), [2, 0]);
+        assert_eq!(app.selected, 3, "selection moves onto a GPU that's still drawn");
+
+        app.on_key(key('N'));
+        app.on_key(key('N'));
+        assert_eq!(app.top_n, None);
+        assert!(app.collapsed().is_empty());
+    }
+
+    #[test]
+    fn sort_and_filter_reorder_display_without_touching_indices() {
+        let mut app = app(vec![vec!["a", "b", "c"]]);
+        app.on_tick();
+        for (gpu, (temp, util)) in app.metrics.iter_mut().zip([(60.0, 0.0), (80.0, 50.0), (70.0, 90.0)]) {
+            gpu.temperature_c = Some(temp);
+            gpu.utilization_pct = Some(util);
+        }
+
+        assert_eq!(app.display_order(), [0, 1, 2]);
+        app.on_key(KeyEvent::from(KeyCode::Char('o')));
+        assert_eq!(app.sort, SortKey::Temperature);
+        assert_eq!(app.display_order(), [1, 2, 0]);
+        // Right follows the on-screen order, selection stays a device index
+        app.on_key(KeyEvent::from(KeyCode::Right));
+        assert_eq!(app.selected, 1);
+
+        app.filter = GpuFilter::Busy;
+        app.metrics[1].utilization_pct = Some(1.0);
+        assert_eq!(app.display_order(), [2]);
+        app.keep_selection_shown();
+        assert_eq!(app.selected, 2);
+    }
+
+    #[test]
+    fn panicking_backend_is_a_failed_sample() {
+        struct Buggy;
+        impl GpuBackend for Buggy {
+            fn name(&self) -> &str {
+                "buggy"
+            }
+
+            fn sample(&mut self) -> io::Result<Vec<GpuMetrics>> {
+                let gpus: Vec<GpuMetrics> = vec![];
+                Ok(vec![gpus[3].clone()])
+            }
+        }
+
+        let mut app = App::new(Box::new(Buggy), Duration::from_millis(50), Duration::from_secs(60)).unwrap();
+        app.on_tick();
+        let status = app.status.clone().unwrap_or_default();
+        assert!(status.starts_with("sample failed: backend panicked: index out of bounds"), "{status}");
+        // Still sampling afterwards
+        app.on_tick();
+        assert!(app.status.is_some_and(|s| !s.contains("exited")));
+    }
+
+    #[test]
+    fn slow_backend_does_not_block_the_tick() {
+        use std::sync::mpsc;
+
+        /// First sample is immediate, every later one waits for a go-ahead.
+        struct Gated(mpsc::Receiver<()>, bool);
+        impl GpuBackend for Gated {
+            fn name(&self) -> &str {
+                "gated"
+            }
+
+            fn sample(&mut self) -> io::Result<Vec<GpuMetrics>> {
+                if std::mem::replace(&mut self.1, true) {
+                    self.0.recv().map_err(io::Error::other)?;
+                }
+                Ok(vec![GpuMetrics { name: "a".into(), ..Default::default() }])
+            }
+        }
+
+        let (go, gate) = mpsc::channel();
+        let mut app = App::new(Box::new(Gated(gate, false)), Duration::from_millis(50), Duration::from_secs(60)).unwrap();
+        app.on_tick();
+        let first = app.metrics[0].timestamp;
+
+        let started = Instant::now();
+        app.on_tick();
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(app.metrics[0].timestamp, first);
+
+        go.send(()).unwrap();
+        app.on_tick();
+        assert!(app.metrics[0].timestamp > first);
+    }
+}
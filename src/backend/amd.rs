@@ -0,0 +1,256 @@
+//! AMD cards via amdgpu sysfs/hwmon.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::metrics::{GpuMetrics, Vendor};
+use super::GpuBackend;
+use super::fdinfo::{EngineUsage, ProcessUsage, drm_clients, pci_slot};
+use super::sysfs::{DRM_ROOT, bytes_to_mb, drm_cards, first_hwmon, driver_version, hwmon_fan_control, hwmon_fans, pcie_link, read_denied, read_num, read_trimmed};
+
+const AMD_VENDOR_ID: &str = "0x1002";
+
+/// Real AMD readings straight from the amdgpu driver's sysfs nodes.
+///
+/// Cards are re-enumerated on every sample, and any node that's missing or
+/// unreadable just turns into `None` for that metric.
+pub struct AmdSysfsBackend {
+    /// `DRM_ROOT`, unless pointed at a fixture tree.
+    root: PathBuf,
+    driver_version: Option<String>,
+    /// `vbios_version` per device directory, read the first time a card shows up.
+    vbios: HashMap<PathBuf, Option<String>>,
+    /// amdgpu has no process query, so the process list comes from fdinfo.
+    processes: ProcessUsage,
+    /// The per-engine breakdown, from the same clients. Only covers the
+    /// processes we can read, unlike `gpu_busy_percent`, which is why it
+    /// isn't the headline.
+    engines: EngineUsage,
+}
+
+impl AmdSysfsBackend {
+    /// Fails if there's no amdgpu card at all, so callers can fall back to something else.
+    pub fn new() -> io::Result<Self> {
+        Self::with_root(DRM_ROOT)
+    }
+
+    /// Like `new`, but reads cards from `root` instead of `/sys/class/drm`.
+    pub fn with_root(root: impl Into<PathBuf>) -> io::Result<Self> {
+        let root = root.into();
+        if amd_cards(&root)?.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no AMD GPUs found under {}", root.display()),
+            ));
+        }
+        Ok(Self {
+            root,
+            driver_version: driver_version("amdgpu"),
+            vbios: HashMap::new(),
+            processes: ProcessUsage::default(),
+            engines: EngineUsage::default(),
+        })
+    }
+}
+
+impl GpuBackend for AmdSysfsBackend {
+    fn name(&self) -> &str {
+        "AMD sysfs"
+    }
+
+    fn sample(&mut self) -> io::Result<Vec<GpuMetrics>> {
+        let mut out = vec![];
+        let clients = drm_clients();
+        let now = Instant::now();
+        let mut processes = self.processes.update(&clients, now);
+        let mut engines = self.engines.update(&clients, now);
+        for (card, device) in amd_cards(&self.root)? {
+            let mut gpu = read_amd_card(&card, &device);
+            if let Some(pdev) = pci_slot(&device) {
+                gpu.processes = processes.remove(&pdev).unwrap_or_default();
+                gpu.engine_utilization = engines.remove(&pdev).unwrap_or_default();
+            }
+            gpu.driver_version = self.driver_version.clone();
+            gpu.vbios_version = self
+                .vbios
+                .entry(device)
+                .or_insert_with_key(|device| read_trimmed(&device.join("vbios_version")))
+                .clone();
+            out.push(gpu);
+        }
+        Ok(out)
+    }
+}
+
+fn amd_cards(root: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+    drm_cards(root, AMD_VENDOR_ID)
+}
+
+fn read_amd_card(card: &str, device: &Path) -> GpuMetrics {
+    let hwmon = first_hwmon(device);
+    let hw = |file: &str| hwmon.as_ref().map(|h| h.join(file));
+
+    // amdgpu hwmon: temp1 = edge, temp2 = junction, temp3 = mem (millidegrees)
+    let temp = |file: &str| hw(file).and_then(|p| read_num::<f32>(&p)).map(|t| t / 1000.0);
+    // freqN_input is in Hz
+    let clock = |file: &str| hw(file).and_then(|p| read_num::<u64>(&p)).map(|hz| (hz / 1_000_000) as u32);
+    let vram = |file: &str| read_num::<u64>(&device.join(file)).map(bytes_to_mb);
+
+    // power1_average is gone on some newer kernels, power1_input replaced it (microwatts)
+    let power_w = hw("power1_average")
+        .and_then(|p| read_num::<f32>(&p))
+        .or_else(|| hw("power1_input").and_then(|p| read_num::<f32>(&p)))
+        .map(|uw| uw / 1_000_000.0);
+    let power_limit_w = hw("power1_cap").and_then(|p| read_num::<f32>(&p)).map(|uw| uw / 1_000_000.0);
+
+    let (pcie_gen, pcie_width) = pcie_link(device);
+    let temperature_c = temp("temp1_input");
+    // A hwmon directory we can't even list hides every sensor in it
+    let denied = |files: &[&str]| match &hwmon {
+        Some(h) => files.iter().any(|f| read_denied(&h.join(f))),
+        None => fs::read_dir(device.join("hwmon")).is_err_and(|e| e.kind() == io::ErrorKind::PermissionDenied),
+    };
+    let mut unreadable = vec![];
+    if power_w.is_none() && denied(&["power1_average", "power1_input"]) {
+        unreadable.push("power".to_string());
+    }
+    if temperature_c.is_none() && denied(&["temp1_input"]) {
+        unreadable.push("temperature".to_string());
+    }
+    let throttle_reasons = amd_throttle_reasons(hwmon.as_deref(), power_w, power_limit_w);
+
+    GpuMetrics {
+        name: read_trimmed(&device.join("product_name"))
+            .filter(|n| !n.is_empty())
+            .unwrap_or_else(|| format!("AMD GPU ({card})")),
+        vendor: Vendor::Amd,
+        temperature_c,
+        junction_temp_c: temp("temp2_input"),
+        mem_temp_c: temp("temp3_input"),
+        utilization_pct: read_num(&device.join("gpu_busy_percent")),
+        mem_utilization_pct: read_num(&device.join("mem_busy_percent")),
+        vram_used_mb: vram("mem_info_vram_used"),
+        vram_total_mb: vram("mem_info_vram_total"),
+        power_w,
+        power_limit_w,
+        fans: hwmon.as_deref().map(hwmon_fans).unwrap_or_default(),
+        fan_control: hwmon.as_deref().and_then(hwmon_fan_control),
+        core_clock_mhz: clock("freq1_input"),
+        mem_clock_mhz: clock("freq2_input"),
+        core_clock_max_mhz: dpm_max_mhz(&device.join("pp_dpm_sclk")),
+        mem_clock_max_mhz: dpm_max_mhz(&device.join("pp_dpm_mclk")),
+        perf_state: amd_perf_state(device),
+        ecc_errors: umc_uncorrected(&device.join("ras").join("umc_err_count")),
+        pcie_gen,
+        pcie_width,
+        throttle_reasons,
+        unreadable,
+        ..Default::default()
+    }
+}
+
+/// Uncorrectable VRAM errors from the RAS counters (`ue: 0\nce: 3`), which
+/// only exist on cards with ECC (Instinct, some Radeon Pro).
+fn umc_uncorrected(path: &Path) -> Option<u64> {
+    parse_umc_uncorrected(&fs::read_to_string(path).ok()?)
+}
+
+fn parse_umc_uncorrected(counts: &str) -> Option<u64> {
+    counts.lines().find_map(|line| line.strip_prefix("ue:")?.trim().parse().ok())
+}
+
+/// Highest level in a `pp_dpm_*` table, which looks like
+/// `0: 500Mhz\n1: 1200Mhz\n2: 2400Mhz *` (the `*` marks the current level).
+fn dpm_max_mhz(path: &Path) -> Option<u32> {
+    parse_dpm_max_mhz(&fs::read_to_string(path).ok()?)
+}
+
+fn parse_dpm_max_mhz(table: &str) -> Option<u32> {
+    table
+        .lines()
+        .filter_map(|line| {
+            let (_, freq) = line.split_once(':')?;
+            let freq = freq.trim().trim_end_matches('*').trim();
+            freq.strip_suffix("Mhz").or_else(|| freq.strip_suffix("MHz"))?.trim().parse().ok()
+        })
+        .max()
+}
+
+/// "auto, sclk level 1 (top 2)": the forced performance level ("auto" unless
+/// someone pinned it) and which `pp_dpm_sclk` level the core is at.
+fn amd_perf_state(device: &Path) -> Option<String> {
+    let forced = read_trimmed(&device.join("power_dpm_force_performance_level")).filter(|l| !l.is_empty());
+    let sclk = fs::read_to_string(device.join("pp_dpm_sclk")).ok().and_then(|t| parse_dpm_level(&t));
+    let sclk = sclk.map(|(level, top)| format!("sclk level {level} (top {top})"));
+    match (forced, sclk) {
+        (Some(forced), Some(sclk)) => Some(format!("{forced}, {sclk}")),
+        (forced, sclk) => forced.or(sclk),
+    }
+}
+
+/// The `*`-marked level in a `pp_dpm_*` table and the highest level number.
+fn parse_dpm_level(table: &str) -> Option<(u32, u32)> {
+    let levels: Vec<(u32, bool)> = table
+        .lines()
+        .filter_map(|line| {
+            let (level, freq) = line.split_once(':')?;
+            Some((level.trim().parse().ok()?, freq.trim_end().ends_with('*')))
+        })
+        .collect();
+    let current = levels.iter().find(|(_, current)| *current)?.0;
+    Some((current, levels.iter().map(|(level, _)| *level).max()?))
+}
+
+/// amdgpu only reports throttle status inside the binary `gpu_metrics` blob,
+/// so infer the two common causes from hwmon instead: a sensor at its
+/// `tempN_crit` (where amdgpu starts throttling; `_emergency` is shutdown) and
+/// power pinned at `power1_cap`.
+fn amd_throttle_reasons(hwmon: Option<&Path>, power_w: Option<f32>, cap: Option<f32>) -> Vec<String> {
+    let Some(hwmon) = hwmon else {
+        return vec![];
+    };
+    let mut reasons = vec![];
+    let at_crit = (1..=3).any(|n| {
+        let input = read_num::<f32>(&hwmon.join(format!("temp{n}_input")));
+        let crit = read_num::<f32>(&hwmon.join(format!("temp{n}_crit")));
+        matches!((input, crit), (Some(t), Some(c)) if c > 0.0 && t >= c)
+    });
+    if at_crit {
+        reasons.push("thermal".to_string());
+    }
+    // within 2% counts as riding the cap
+    if let (Some(p), Some(cap)) = (power_w, cap)
+        && cap > 0.0
+        && p >= cap * 0.98
+    {
+        reasons.push("power".to_string());
+    }
+    reasons
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dpm_table_max_ignores_current_marker() {
+        assert_eq!(parse_dpm_max_mhz("0: 500Mhz\n1: 1800Mhz *\n2: 2400Mhz\n"), Some(2400));
+        assert_eq!(parse_dpm_max_mhz("0: 96Mhz *\n"), Some(96));
+        assert_eq!(parse_dpm_max_mhz(""), None);
+    }
+
+    #[test]
+    fn dpm_table_level_is_the_marked_one() {
+        assert_eq!(parse_dpm_level("0: 500Mhz\n1: 1800Mhz *\n2: 2400Mhz\n"), Some((1, 2)));
+        assert_eq!(parse_dpm_level("0: 500Mhz\n1: 1800Mhz\n"), None);
+    }
+
+    #[test]
+    fn ras_counts_report_the_uncorrectable_line() {
+        assert_eq!(parse_umc_uncorrected("ue: 2\nce: 17\n"), Some(2));
+        assert_eq!(parse_umc_uncorrected("ce: 17\n"), None);
+    }
+}
@@ -0,0 +1,332 @@
+//! Apple Silicon integrated GPU via IOReport (the private framework behind
+//! `powermetrics`) plus the DVFS table IOKit publishes on the `pmgr` node.
+//!
+//! IOReport hands out cumulative residency/energy counters, so every sample is a
+//! delta against the previous one: utilization and power read `None` the first time.
+//! There's no public GPU temperature or fan sensor, and VRAM is unified memory
+//! shared with the CPU, so those stay `None` rather than showing system RAM.
+
+use std::ffi::{CStr, c_char, c_void};
+use std::io;
+use std::process::Command;
+use std::ptr;
+use std::time::Instant;
+
+use core_foundation::array::{CFArrayGetCount, CFArrayGetValueAtIndex, CFArrayRef};
+use core_foundation::base::{CFAllocatorRef, CFType, CFTypeRef, TCFType, kCFAllocatorDefault};
+use core_foundation::data::CFData;
+use core_foundation::dictionary::{
+    CFDictionary, CFDictionaryCreateMutableCopy, CFDictionaryGetCount, CFDictionaryGetValue,
+    CFDictionaryRef, CFMutableDictionaryRef,
+};
+use core_foundation::string::{CFString, CFStringRef};
+
+use crate::metrics::{GpuMetrics, Vendor};
+use super::GpuBackend;
+
+type IOReportSubscriptionRef = CFTypeRef;
+
+#[link(name = "IOReport", kind = "dylib")]
+unsafe extern "C" {
+    fn IOReportCopyChannelsInGroup(
+        group: CFStringRef,
+        subgroup: CFStringRef,
+        a: u64,
+        b: u64,
+        c: u64,
+    ) -> CFDictionaryRef;
+    fn IOReportMergeChannels(a: CFDictionaryRef, b: CFDictionaryRef, nil: CFTypeRef);
+    fn IOReportCreateSubscription(
+        a: *const c_void,
+        desired: CFMutableDictionaryRef,
+        subbed: *mut CFMutableDictionaryRef,
+        channel_id: u64,
+        b: CFTypeRef,
+    ) -> IOReportSubscriptionRef;
+    fn IOReportCreateSamples(
+        sub: IOReportSubscriptionRef,
+        channels: CFMutableDictionaryRef,
+        a: CFTypeRef,
+    ) -> CFDictionaryRef;
+    fn IOReportCreateSamplesDelta(
+        prev: CFDictionaryRef,
+        cur: CFDictionaryRef,
+        a: CFTypeRef,
+    ) -> CFDictionaryRef;
+    fn IOReportChannelGetGroup(item: CFDictionaryRef) -> CFStringRef;
+    fn IOReportChannelGetSubGroup(item: CFDictionaryRef) -> CFStringRef;
+    fn IOReportChannelGetChannelName(item: CFDictionaryRef) -> CFStringRef;
+    fn IOReportChannelGetUnitLabel(item: CFDictionaryRef) -> CFStringRef;
+    fn IOReportSimpleGetIntegerValue(item: CFDictionaryRef, index: i32) -> i64;
+    fn IOReportStateGetCount(item: CFDictionaryRef) -> i32;
+    fn IOReportStateGetNameForIndex(item: CFDictionaryRef, index: i32) -> CFStringRef;
+    fn IOReportStateGetResidency(item: CFDictionaryRef, index: i32) -> i64;
+}
+
+#[link(name = "IOKit", kind = "framework")]
+unsafe extern "C" {
+    fn IOServiceMatching(name: *const c_char) -> CFMutableDictionaryRef;
+    fn IOServiceGetMatchingServices(port: u32, matching: CFDictionaryRef, iter: *mut u32) -> i32;
+    fn IOIteratorNext(iter: u32) -> u32;
+    fn IORegistryEntryGetName(entry: u32, name: *mut c_char) -> i32;
+    fn IORegistryEntryCreateCFProperties(
+        entry: u32,
+        props: *mut CFMutableDictionaryRef,
+        allocator: CFAllocatorRef,
+        options: u32,
+    ) -> i32;
+    fn IOObjectRelease(obj: u32) -> i32;
+}
+
+/// A previous IOReport sample plus when it was taken.
+struct Sample {
+    dict: CFDictionary,
+    at: Instant,
+}
+
+pub struct AppleBackend {
+    name: String,
+    subscription: CFType,
+    channels: CFDictionary,
+    /// Active P-state frequencies (MHz), P1 first.
+    gpu_freqs_mhz: Vec<u32>,
+    prev: Option<Sample>,
+}
+
+// SAFETY: the CF objects are only ever touched by whichever thread owns the
+// backend (the sampler), one call at a time. CoreFoundation retain/release is
+// thread-safe and an IOReport subscription isn't tied to the creating thread.
+unsafe impl Send for AppleBackend {}
+
+impl AppleBackend {
+    /// Fails off Apple Silicon (no GPU channels to subscribe to), so callers can fall back.
+    pub fn new() -> io::Result<Self> {
+        let unavailable =
+            |what: &str| io::Error::new(io::ErrorKind::NotFound, format!("IOReport: {what}"));
+        unsafe {
+            let gpu =
+                copy_channels("GPU Stats").ok_or_else(|| unavailable("no GPU Stats channels"))?;
+            if let Some(energy) = copy_channels("Energy Model") {
+                IOReportMergeChannels(
+                    gpu.as_concrete_TypeRef(),
+                    energy.as_concrete_TypeRef(),
+                    ptr::null(),
+                );
+            }
+            let size = CFDictionaryGetCount(gpu.as_concrete_TypeRef());
+            let desired =
+                CFDictionaryCreateMutableCopy(kCFAllocatorDefault, size, gpu.as_concrete_TypeRef());
+            let desired: CFDictionary =
+                CFDictionary::wrap_under_create_rule(desired as CFDictionaryRef);
+
+            let mut subbed: CFMutableDictionaryRef = ptr::null_mut();
+            let sub = IOReportCreateSubscription(
+                ptr::null(),
+                desired.as_concrete_TypeRef() as CFMutableDictionaryRef,
+                &mut subbed,
+                0,
+                ptr::null(),
+            );
+            if sub.is_null() || subbed.is_null() {
+                return Err(unavailable("subscription refused"));
+            }
+
+            Ok(Self {
+                name: chip_name()
+                    .map(|c| format!("{c} GPU"))
+                    .unwrap_or_else(|| "Apple GPU".into()),
+                subscription: CFType::wrap_under_create_rule(sub),
+                channels: CFDictionary::wrap_under_create_rule(subbed as CFDictionaryRef),
+                gpu_freqs_mhz: gpu_dvfs_mhz(),
+                prev: None,
+            })
+        }
+    }
+}
+
+impl GpuBackend for AppleBackend {
+    fn name(&self) -> &str {
+        "IOReport"
+    }
+
+    fn sample(&mut self) -> io::Result<Vec<GpuMetrics>> {
+        let now = unsafe {
+            let dict = IOReportCreateSamples(
+                self.subscription.as_CFTypeRef(),
+                self.channels.as_concrete_TypeRef() as CFMutableDictionaryRef,
+                ptr::null(),
+            );
+            if dict.is_null() {
+                return Err(io::Error::other("IOReport returned no samples"));
+            }
+            Sample {
+                dict: CFDictionary::wrap_under_create_rule(dict),
+                at: Instant::now(),
+            }
+        };
+
+        let mut gpu = GpuMetrics {
+            name: self.name.clone(),
+            vendor: Vendor::Apple,
+            core_clock_max_mhz: self.gpu_freqs_mhz.iter().max().copied(),
+            ..Default::default()
+        };
+        if let Some(prev) = &self.prev {
+            let secs = now.at.duration_since(prev.at).as_secs_f32();
+            unsafe {
+                let delta = IOReportCreateSamplesDelta(
+                    prev.dict.as_concrete_TypeRef(),
+                    now.dict.as_concrete_TypeRef(),
+                    ptr::null(),
+                );
+                if !delta.is_null() {
+                    let delta = CFDictionary::wrap_under_create_rule(delta);
+                    read_delta(&delta, &self.gpu_freqs_mhz, secs, &mut gpu);
+                }
+            }
+        }
+        self.prev = Some(now);
+        Ok(vec![gpu])
+    }
+}
+
+unsafe fn copy_channels(group: &str) -> Option<CFDictionary> {
+    let group = CFString::new(group);
+    let dict =
+        unsafe { IOReportCopyChannelsInGroup(group.as_concrete_TypeRef(), ptr::null(), 0, 0, 0) };
+    (!dict.is_null()).then(|| unsafe { CFDictionary::wrap_under_create_rule(dict) })
+}
+
+fn cf_str(s: CFStringRef) -> String {
+    if s.is_null() {
+        return String::new();
+    }
+    unsafe { CFString::wrap_under_get_rule(s) }.to_string()
+}
+
+/// Walks the `IOReportChannels` array of a delta sample and fills in utilization,
+/// the residency-weighted active clock, and power.
+unsafe fn read_delta(delta: &CFDictionary, freqs_mhz: &[u32], secs: f32, gpu: &mut GpuMetrics) {
+    let key = CFString::from_static_string("IOReportChannels");
+    let items = unsafe { CFDictionaryGetValue(delta.as_concrete_TypeRef(), key.as_CFTypeRef()) }
+        as CFArrayRef;
+    if items.is_null() {
+        return;
+    }
+    for i in 0..unsafe { CFArrayGetCount(items) } {
+        let item = unsafe { CFArrayGetValueAtIndex(items, i) } as CFDictionaryRef;
+        let (group, subgroup, channel) = unsafe {
+            (
+                cf_str(IOReportChannelGetGroup(item)),
+                cf_str(IOReportChannelGetSubGroup(item)),
+                cf_str(IOReportChannelGetChannelName(item)),
+            )
+        };
+        if group == "GPU Stats" && subgroup == "GPU Performance States" && channel == "GPUPH" {
+            let (util, mhz) = unsafe { gpu_residency(item, freqs_mhz) };
+            gpu.utilization_pct = util;
+            gpu.core_clock_mhz = mhz;
+        } else if group == "Energy Model" && channel == "GPU Energy" && secs > 0.0 {
+            let value = unsafe { IOReportSimpleGetIntegerValue(item, 0) } as f32;
+            let joules = match cf_str(unsafe { IOReportChannelGetUnitLabel(item) }).as_str() {
+                "mJ" => value / 1e3,
+                "uJ" => value / 1e6,
+                "nJ" => value / 1e9,
+                _ => continue,
+            };
+            gpu.power_w = Some(joules / secs);
+        }
+    }
+}
+
+/// GPUPH states are `OFF`, `IDLE`, then `P1..Pn`; busy is the share of time in a
+/// P-state, and the clock is those states' frequencies weighted by residency.
+unsafe fn gpu_residency(item: CFDictionaryRef, freqs_mhz: &[u32]) -> (Option<f32>, Option<u32>) {
+    let mut total = 0i64;
+    let mut active = 0i64;
+    let mut weighted = 0f64;
+    let mut pstate = 0usize;
+    for i in 0..unsafe { IOReportStateGetCount(item) } {
+        let name = cf_str(unsafe { IOReportStateGetNameForIndex(item, i) });
+        let residency = unsafe { IOReportStateGetResidency(item, i) };
+        total += residency;
+        if matches!(name.as_str(), "OFF" | "IDLE" | "DOWN") {
+            continue;
+        }
+        active += residency;
+        if let Some(&mhz) = freqs_mhz.get(pstate) {
+            weighted += mhz as f64 * residency as f64;
+        }
+        pstate += 1;
+    }
+    if total <= 0 {
+        return (None, None);
+    }
+    let util = (active as f64 / total as f64 * 100.0) as f32;
+    let mhz = (active > 0 && !freqs_mhz.is_empty()).then(|| (weighted / active as f64) as u32);
+    (Some(util), mhz)
+}
+
+/// GPU P-state frequencies from `pmgr`'s `voltage-states9`: `(freq Hz, µV)` u32
+/// pairs, little endian, with a leading zero-frequency "off" entry we drop.
+/// Empty if the node or property isn't there (newer chips renamed it).
+fn gpu_dvfs_mhz() -> Vec<u32> {
+    let Some(data) = pmgr_property("voltage-states9") else {
+        return vec![];
+    };
+    data.chunks_exact(8)
+        .map(|pair| u32::from_le_bytes([pair[0], pair[1], pair[2], pair[3]]) / 1_000_000)
+        .filter(|&mhz| mhz > 0)
+        .collect()
+}
+
+fn pmgr_property(key: &str) -> Option<Vec<u8>> {
+    unsafe {
+        let mut iter = 0u32;
+        let matching = IOServiceMatching(c"AppleARMIODevice".as_ptr());
+        if IOServiceGetMatchingServices(0, matching as CFDictionaryRef, &mut iter) != 0 {
+            return None;
+        }
+        let mut found = None;
+        loop {
+            let entry = IOIteratorNext(iter);
+            if entry == 0 {
+                break;
+            }
+            let mut name = [0 as c_char; 128];
+            let is_pmgr = IORegistryEntryGetName(entry, name.as_mut_ptr()) == 0
+                && CStr::from_ptr(name.as_ptr()).to_bytes() == b"pmgr";
+            if is_pmgr {
+                let mut props: CFMutableDictionaryRef = ptr::null_mut();
+                if IORegistryEntryCreateCFProperties(entry, &mut props, kCFAllocatorDefault, 0) == 0
+                    && !props.is_null()
+                {
+                    let props: CFDictionary =
+                        CFDictionary::wrap_under_create_rule(props as CFDictionaryRef);
+                    let key = CFString::new(key);
+                    let value =
+                        CFDictionaryGetValue(props.as_concrete_TypeRef(), key.as_CFTypeRef());
+                    if !value.is_null() {
+                        found = Some(CFData::wrap_under_get_rule(value as _).bytes().to_vec());
+                    }
+                }
+            }
+            IOObjectRelease(entry);
+            if found.is_some() {
+                break;
+            }
+        }
+        IOObjectRelease(iter);
+        found
+    }
+}
+
+/// e.g. "Apple M2 Pro"
+fn chip_name() -> Option<String> {
+    let out = Command::new("sysctl")
+        .args(["-n", "machdep.cpu.brand_string"])
+        .output()
+        .ok()?;
+    let name = String::from_utf8(out.stdout).ok()?.trim().to_string();
+    (!name.is_empty()).then_some(name)
+}
@@ -0,0 +1,129 @@
+//! Several backends at once, for machines with cards from more than one
+//! vendor (an NVIDIA dGPU next to an AMD iGPU, say).
+
+use std::io;
+use std::time::Instant;
+
+use crate::metrics::GpuMetrics;
+use super::{BackendHealth, GpuBackend};
+
+/// Samples every backend in turn and lists their GPUs one after another, in
+/// the order the backends were given.
+pub struct CompositeBackend {
+    backends: Vec<Box<dyn GpuBackend>>,
+    name: String,
+    /// One per backend, in the same order.
+    health: Vec<BackendHealth>,
+    /// Each backend's latest cards, to stand in for them when it fails.
+    last: Vec<Vec<GpuMetrics>>,
+}
+
+impl CompositeBackend {
+    pub fn new(backends: Vec<Box<dyn GpuBackend>>) -> Self {
+        let name = backends.iter().map(|b| b.name()).collect::<Vec<_>>().join(" + ");
+        let health = backends
+            .iter()
+            .map(|b| BackendHealth { name: b.name().to_string(), ok: true, last_ok: None })
+            .collect();
+        let last = vec![vec![]; backends.len()];
+        Self { backends, name, health, last }
+    }
+}
+
+impl GpuBackend for CompositeBackend {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn is_mock(&self) -> bool {
+        self.backends.iter().all(|b| b.is_mock())
+    }
+
+    /// One backend failing only costs its own cards, which are resent as
+    /// they last read and marked disconnected, so everything after them
+    /// keeps its index. The sample errors only when every backend fails.
+    fn sample(&mut self) -> io::Result<Vec<GpuMetrics>> {
+        let mut gpus = vec![];
+        let mut errors = vec![];
+        for ((backend, health), last) in self.backends.iter_mut().zip(&mut self.health).zip(&mut self.last) {
+            let result = backend.sample();
+            health.ok = result.is_ok();
+            match result {
+                Ok(more) => {
+                    last.clone_from(&more);
+                    gpus.extend(more);
+                    health.last_ok = Some(Instant::now());
+                }
+                Err(e) => {
+                    errors.push(format!("{}: {e}", backend.name()));
+                    gpus.extend(last.iter().cloned().map(|gpu| GpuMetrics { disconnected: true, ..gpu }));
+                }
+            }
+        }
+        if !errors.is_empty() && errors.len() == self.backends.len() {
+            return Err(io::Error::other(errors.join("; ")));
+        }
+        Ok(gpus)
+    }
+
+    fn health(&self) -> Vec<BackendHealth> {
+        self.health.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::FakeBackend;
+
+    struct Broken;
+
+    impl GpuBackend for Broken {
+        fn name(&self) -> &str {
+            "broken"
+        }
+
+        fn sample(&mut self) -> io::Result<Vec<GpuMetrics>> {
+            Err(io::Error::other("device lost"))
+        }
+    }
+
+    /// One card, then errors from the second sample on.
+    struct Flaky(bool);
+
+    impl GpuBackend for Flaky {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        fn sample(&mut self) -> io::Result<Vec<GpuMetrics>> {
+            if std::mem::replace(&mut self.0, true) {
+                return Err(io::Error::other("device lost"));
+            }
+            Ok(vec![GpuMetrics { name: "flaky".into(), ..Default::default() }])
+        }
+    }
+
+    #[test]
+    fn one_failing_backend_keeps_the_others() {
+        let mut both = CompositeBackend::new(vec![Box::new(Broken), Box::new(FakeBackend::new())]);
+        assert_eq!(both.name(), "broken + mock");
+        assert!(!both.is_mock());
+        assert_eq!(both.sample().unwrap().len(), 1);
+        let health = both.health();
+        assert_eq!((health[0].name.as_str(), health[0].ok, health[0].last_ok), ("broken", false, None));
+        assert!(health[1].ok && health[1].last_ok.is_some());
+
+        // Once a backend has had cards, they hold their places when it fails
+        let mut flaky = CompositeBackend::new(vec![Box::new(Flaky(false)), Box::new(FakeBackend::new())]);
+        let names = |gpus: &[GpuMetrics]| gpus.iter().map(|g| (g.name.clone(), g.disconnected)).collect::<Vec<_>>();
+        let before = names(&flaky.sample().unwrap());
+        assert_eq!(before, [("flaky".to_string(), false), ("AMD Radeon (mock)".to_string(), false)]);
+        let after = names(&flaky.sample().unwrap());
+        assert_eq!(after, [("flaky".to_string(), true), ("AMD Radeon (mock)".to_string(), false)]);
+
+        let mut neither = CompositeBackend::new(vec![Box::new(Broken), Box::new(Broken)]);
+        let err = neither.sample().unwrap_err().to_string();
+        assert_eq!(err, "broken: device lost; broken: device lost");
+    }
+}
@@ -0,0 +1,132 @@
+//! Mock backend for development on machines without a supported GPU.
+
+use std::io;
+use std::time::Instant;
+
+use crate::metrics::{FanControl, FanMetrics, GpuMetrics, Vendor};
+use super::GpuBackend;
+
+/// Fake sampler for macOS/dev. Later I gotta replace this with:
+/// - AMD sysfs reader, OR
+/// - rocm-smi JSON parser, OR
+/// - Intel backend, etc.
+fn sample_fake(counter: u64) -> Vec<GpuMetrics> {
+    // Give it a little “motion” so you can see updates.
+    let temp = 45.0 + ((counter % 30) as f32) * 0.3;      // ~45–54C
+    let util = (counter % 100) as f32;                    // 0–99%
+    let used = 1200 + (counter as u32 % 800);             // 1200–1999 MB
+    let total = 16_384;
+    let junction = temp + 12.0 + ((counter % 10) as f32) * 0.2; // hotspot higher
+    let mem_temp = temp + 6.0;                                  // vram a bit higher
+    let core_clk = 800 + (counter as u32 % 1600);               // 800–2399 MHz
+    let mem_clk  = 1000 + (counter as u32 % 800);  
+
+    vec![GpuMetrics {
+        name: "AMD Radeon (mock)".to_string(),
+        vendor: Vendor::Amd,
+        temperature_c: Some(temp),
+        utilization_pct: Some(util),
+        mem_utilization_pct: Some(util * 0.6),
+        // amdgpu's fdinfo engine names; gfx is the busiest, like the headline
+        engine_utilization: vec![
+            ("compute".to_string(), util * 0.4),
+            ("dma".to_string(), (counter % 7) as f32),
+            ("gfx".to_string(), util),
+        ],
+        vram_used_mb: Some(used),
+        vram_total_mb: Some(total),
+        power_w: Some(90.0 + (counter % 20) as f32),
+        gpu_chip_power_w: None,
+        power_limit_w: Some(110.0),
+        fans: (0..2)
+            .map(|i| {
+                let rpm = 1200 + 50 * i + (counter as u32 % 400);
+                FanMetrics { index: i, rpm: Some(rpm), pct: Some(rpm as f32 / 3200.0 * 100.0) }
+            })
+            .collect(),
+        fan_control: Some(FanControl::Auto),
+        junction_temp_c: Some(junction),
+        mem_temp_c: Some(mem_temp),
+        core_clock_mhz: Some(core_clk),
+        mem_clock_mhz: Some(mem_clk),
+        core_clock_max_mhz: Some(2400),
+        mem_clock_max_mhz: Some(1800),
+        perf_state: Some(format!("auto, sclk level {} (top 2)", (core_clk - 800) / 534)),
+        ecc_errors: None,
+        pcie_gen: Some(4),
+        pcie_width: Some(16),
+        driver_version: Some("mock 1.0".to_string()),
+        vbios_version: None,
+        // Pretend to ride the power cap at the top of the power wave
+        throttle_reasons: if counter % 20 >= 18 { vec!["power".to_string()] } else { vec![] },
+        unreadable: vec![],
+        processes: vec![],
+        disconnected: false,
+        timestamp: Instant::now(),
+    }]
+}
+
+/// Mock backend: wraps `sample_fake` with its own counter so the numbers keep moving.
+#[derive(Default)]
+pub struct FakeBackend {
+    counter: u64,
+}
+
+impl FakeBackend {
+    pub fn new() -> Self {
+        Self { counter: 0 }
+    }
+}
+
+impl GpuBackend for FakeBackend {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    fn is_mock(&self) -> bool {
+        true
+    }
+
+    fn sample(&mut self) -> io::Result<Vec<GpuMetrics>> {
+        let metrics = sample_fake(self.counter);
+        self.counter += 1;
+        Ok(metrics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GOLDEN: &str = "src/backend/testdata/fake_samples.json";
+
+    /// The first two mock samples as JSON, minus the wall-clock timestamps.
+    /// Via a string, so f32s read back as written (45.3, not 45.29999923706055).
+    fn first_samples() -> serde_json::Value {
+        let text = serde_json::to_string(&[sample_fake(0), sample_fake(1)]).unwrap();
+        let mut json: serde_json::Value = serde_json::from_str(&text).unwrap();
+        for gpu in json.as_array_mut().unwrap().iter_mut().flat_map(|s| s.as_array_mut().unwrap()) {
+            gpu.as_object_mut().unwrap().remove("timestamp_ms");
+        }
+        json
+    }
+
+    /// Pins the mock's formulas, which UI tests and benches lean on. After
+    /// changing them on purpose, `GTOP_BLESS=1 cargo test` rewrites the file.
+    #[test]
+    fn mock_samples_match_the_golden_file() {
+        let actual = first_samples();
+        if std::env::var_os("GTOP_BLESS").is_some() {
+            let text = serde_json::to_string_pretty(&actual).unwrap() + "\n";
+            std::fs::write(GOLDEN, text).unwrap();
+            // `include_str!` below still has the old contents until the next build
+            return;
+        }
+        let golden: serde_json::Value = serde_json::from_str(include_str!("testdata/fake_samples.json")).unwrap();
+        assert_eq!(actual, golden, "mock output changed; see {GOLDEN}");
+
+        // And it survives the round trip recordings and --connect put it through
+        let back: Vec<GpuMetrics> = serde_json::from_value(actual[1].clone()).unwrap();
+        assert_eq!(back[0].power_w, sample_fake(1)[0].power_w);
+    }
+}
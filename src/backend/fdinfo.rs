@@ -0,0 +1,284 @@
+//! DRM clients from `/proc/*/fdinfo`: per-fd engine time and memory, which
+//! every recent Linux GPU driver reports the same way
+//! (Documentation/gpu/drm-usage-stats.rst). Intel needs it for utilization at
+//! all; AMD and Intel both use it for the process list.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::metrics::GpuProcess;
+use super::sysfs::process_name;
+
+/// i915 and amdgpu report busy nanoseconds per engine class; xe reports busy
+/// cycles alongside the total cycles elapsed, which is the denominator.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct EngineCounter {
+    busy: u64,
+    total: Option<u64>,
+}
+
+/// One DRM client (open fd) as parsed from fdinfo.
+#[derive(Debug, Default)]
+pub struct DrmClient {
+    /// The first process found holding it; inherited fds show up in several.
+    pub pid: u32,
+    pub pdev: String,
+    id: u64,
+    engines: HashMap<String, EngineCounter>,
+    /// VRAM (or Intel local memory) the client has resident.
+    pub vram_kib: Option<u64>,
+}
+
+/// Busiest engine class over the interval, like the top bar of `intel_gpu_top`.
+fn engine_busy_pct(
+    now: &HashMap<String, EngineCounter>,
+    prev: &HashMap<String, EngineCounter>,
+    elapsed: Duration,
+) -> Option<f32> {
+    let mut classes = BTreeMap::new();
+    add_engine_deltas(&mut classes, now, prev, elapsed);
+    engine_shares(classes).into_iter().map(|(_, pct)| pct).reduce(f32::max)
+}
+
+/// Adds one client's use of each engine class since `prev` to `classes`:
+/// busy time (or cycles) and what it's out of, which is the time elapsed,
+/// or for xe the cycles that went by.
+fn add_engine_deltas(
+    classes: &mut BTreeMap<String, (f64, f64)>,
+    now: &HashMap<String, EngineCounter>,
+    prev: &HashMap<String, EngineCounter>,
+    elapsed: Duration,
+) {
+    for (class, cur) in now {
+        let old = prev.get(class).copied().unwrap_or_default();
+        let total = match (cur.total, old.total) {
+            (Some(c), Some(o)) => c.saturating_sub(o) as f64,
+            (Some(_), None) => continue,
+            _ => elapsed.as_nanos() as f64,
+        };
+        let sum = classes.entry(class.clone()).or_default();
+        sum.0 += cur.busy.saturating_sub(old.busy) as f64;
+        // Total cycles are a per-GT clock, not per client; any client's value will do
+        sum.1 = sum.1.max(total);
+    }
+}
+
+/// Busy over total per class, as a percentage, in class-name order.
+fn engine_shares(classes: BTreeMap<String, (f64, f64)>) -> Vec<(String, f32)> {
+    classes
+        .into_iter()
+        .filter(|&(_, (_, total))| total > 0.0)
+        .map(|(class, (busy, total))| (class, (busy / total * 100.0).clamp(0.0, 100.0) as f32))
+        .collect()
+}
+
+/// PCI address (`0000:03:00.0`) of a card, matching fdinfo's `drm-pdev`.
+pub fn pci_slot(device: &Path) -> Option<String> {
+    Some(fs::canonicalize(device).ok()?.file_name()?.to_string_lossy().into_owned())
+}
+
+/// Every DRM client in the system, deduplicated by `(pdev, drm-client-id)` since
+/// the same fd shows up in every process that inherited it. Processes we can't
+/// read (other users, without root) are silently skipped.
+pub fn drm_clients() -> Vec<DrmClient> {
+    let mut clients: HashMap<(String, u64), DrmClient> = HashMap::new();
+    let Ok(procs) = fs::read_dir("/proc") else {
+        return vec![];
+    };
+    for proc in procs.flatten() {
+        let Some(pid) = proc.file_name().to_str().and_then(|p| p.parse().ok()) else {
+            continue;
+        };
+        let Ok(fds) = fs::read_dir(proc.path().join("fd")) else {
+            continue;
+        };
+        for fd in fds.flatten() {
+            let is_dri = fs::read_link(fd.path()).is_ok_and(|t| t.starts_with("/dev/dri"));
+            if !is_dri {
+                continue;
+            }
+            let info = proc.path().join("fdinfo").join(fd.file_name());
+            if let Some(client) = fs::read_to_string(info).ok().and_then(|s| parse_drm_fdinfo(&s)) {
+                clients.entry((client.pdev.clone(), client.id)).or_insert(DrmClient { pid, ..client });
+            }
+        }
+    }
+    clients.into_values().collect()
+}
+
+/// Parses the DRM usage stats format. Returns `None` for fds that aren't a
+/// DRM client with a pdev and client id.
+fn parse_drm_fdinfo(text: &str) -> Option<DrmClient> {
+    let mut client = DrmClient::default();
+    let mut id = None;
+    for line in text.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        let num = || value.split_whitespace().next()?.parse::<u64>().ok();
+        if key == "drm-pdev" {
+            client.pdev = value.to_string();
+        } else if key == "drm-client-id" {
+            id = num();
+        } else if let Some(class) = key.strip_prefix("drm-engine-") {
+            // "drm-engine-render: 123456 ns" (skip i915's drm-engine-capacity-*)
+            if !class.starts_with("capacity-")
+                && let Some(ns) = num()
+            {
+                client.engines.entry(class.to_string()).or_default().busy = ns;
+            }
+        } else if let Some(class) = key.strip_prefix("drm-total-cycles-") {
+            client.engines.entry(class.to_string()).or_default().total = num();
+        } else if let Some(class) = key.strip_prefix("drm-cycles-") {
+            if let Some(cycles) = num() {
+                client.engines.entry(class.to_string()).or_default().busy = cycles;
+            }
+        } else if matches!(
+            key,
+            // i915, xe, amdgpu, and amdgpu before the common keys
+            "drm-resident-local0" | "drm-resident-vram0" | "drm-resident-vram" | "drm-memory-vram"
+        ) {
+            client.vram_kib = num().map(|n| match value.split_whitespace().nth(1) {
+                Some("GiB") => n * 1024 * 1024,
+                Some("MiB") => n * 1024,
+                Some("KiB") => n,
+                _ => n / 1024,
+            });
+        }
+    }
+    client.id = id?;
+    (!client.pdev.is_empty()).then_some(client)
+}
+
+/// Each card's engine use from DRM clients. Counters are cumulative per
+/// client, so each client is diffed against its own last reading and only
+/// then summed: a client that just opened has no interval to count yet, and
+/// one that exited takes nothing with it from the others.
+#[derive(Default)]
+pub struct EngineUsage {
+    prev: HashMap<(String, u64), (Instant, HashMap<String, EngineCounter>)>,
+}
+
+impl EngineUsage {
+    /// Every engine class's share of the interval per card (by pdev). Cards
+    /// with no client in both this sample and the last are left out.
+    pub fn update(&mut self, clients: &[DrmClient], at: Instant) -> HashMap<String, Vec<(String, f32)>> {
+        let mut cards: HashMap<String, BTreeMap<String, (f64, f64)>> = HashMap::new();
+        let mut next = HashMap::with_capacity(clients.len());
+        for client in clients {
+            let key = (client.pdev.clone(), client.id);
+            if let Some((then, engines)) = self.prev.get(&key) {
+                let classes = cards.entry(client.pdev.clone()).or_default();
+                add_engine_deltas(classes, &client.engines, engines, at.duration_since(*then));
+            }
+            next.insert(key, (at, client.engines.clone()));
+        }
+        // Rebuilt each time so exited clients don't linger
+        self.prev = next;
+        cards.into_iter().map(|(pdev, classes)| (pdev, engine_shares(classes))).collect()
+    }
+}
+
+/// Turns DRM clients into the process list, for drivers without a process
+/// query of their own. Engine time is a counter, so it keeps every client's
+/// last reading to diff against.
+#[derive(Default)]
+pub struct ProcessUsage {
+    prev: HashMap<(String, u64), (Instant, HashMap<String, EngineCounter>)>,
+}
+
+impl ProcessUsage {
+    /// Each card's (by pdev) processes in PID order, with their clients'
+    /// engine use and VRAM summed. Utilization is `None` the first time a
+    /// process shows up.
+    pub fn update(&mut self, clients: &[DrmClient], at: Instant) -> HashMap<String, Vec<GpuProcess>> {
+        let mut cards: HashMap<String, BTreeMap<u32, GpuProcess>> = HashMap::new();
+        let mut next = HashMap::with_capacity(clients.len());
+        for client in clients {
+            let key = (client.pdev.clone(), client.id);
+            let util = self
+                .prev
+                .get(&key)
+                .and_then(|(then, engines)| engine_busy_pct(&client.engines, engines, at.duration_since(*then)));
+            let process = cards.entry(client.pdev.clone()).or_default().entry(client.pid).or_insert_with(|| {
+                GpuProcess {
+                    pid: client.pid,
+                    name: process_name(client.pid).unwrap_or_else(|| "?".into()),
+                    used_mb: None,
+                    util_pct: None,
+                }
+            });
+            let add = |a: Option<f32>, b: Option<f32>| a.map_or(b, |a| Some(a + b.unwrap_or(0.0)));
+            process.util_pct = add(util, process.util_pct).map(|u| u.min(100.0));
+            let vram_mb = client.vram_kib.map(|k| (k / 1024) as u32);
+            process.used_mb = vram_mb.map_or(process.used_mb, |m| Some(m + process.used_mb.unwrap_or(0)));
+            next.insert(key, (at, client.engines.clone()));
+        }
+        // Rebuilt each time so exited clients don't linger
+        self.prev = next;
+        cards.into_iter().map(|(pdev, procs)| (pdev, procs.into_values().collect())).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An amdgpu fdinfo file with 512 MB of VRAM on 0000:03:00.0.
+    fn amdgpu_client(id: u64, pid: u32, gfx_ns: u64, compute_ns: u64) -> DrmClient {
+        let text = format!(
+            "pos:\t0\nflags:\t02100002\ndrm-driver:\tamdgpu\ndrm-client-id:\t{id}\ndrm-pdev:\t0000:03:00.0\n\
+             drm-memory-vram:\t524288 KiB\ndrm-engine-gfx:\t{gfx_ns} ns\ndrm-engine-compute:\t{compute_ns} ns\n"
+        );
+        DrmClient { pid, ..parse_drm_fdinfo(&text).unwrap() }
+    }
+
+    #[test]
+    fn amdgpu_clients_add_up_per_process() {
+        let first = amdgpu_client(17, 4242, 1_000_000_000, 0);
+        assert_eq!((first.pdev.as_str(), first.id, first.vram_kib), ("0000:03:00.0", 17, Some(524288)));
+        assert!(parse_drm_fdinfo("pos:\t0\nflags:\t02\n").is_none());
+
+        let mut usage = ProcessUsage::default();
+        let start = Instant::now();
+        let procs = usage.update(&[first], start);
+        let proc = &procs["0000:03:00.0"][0];
+        assert_eq!((proc.pid, proc.used_mb, proc.util_pct), (4242, Some(512), None));
+
+        // Half a second of gfx time over a one-second sample
+        let procs = usage.update(&[amdgpu_client(17, 4242, 1_500_000_000, 0)], start + Duration::from_secs(1));
+        assert_eq!(procs["0000:03:00.0"][0].util_pct, Some(50.0));
+    }
+
+    #[test]
+    fn clients_opening_or_exiting_dont_skew_the_card() {
+        let client = |id, gfx_ns| amdgpu_client(id, 4242, gfx_ns, 0);
+        let mut usage = EngineUsage::default();
+        let start = Instant::now();
+        assert!(usage.update(&[client(1, 10_000_000_000), client(2, 0)], start).is_empty());
+
+        // Client 1 exits with plenty of busy time behind it, client 3 opens with
+        // a long history; only client 2's quarter second is this interval's
+        let second = start + Duration::from_secs(1);
+        let cards = usage.update(&[client(2, 250_000_000), client(3, 50_000_000_000)], second);
+        let gfx = cards["0000:03:00.0"].iter().find(|(class, _)| class == "gfx").unwrap();
+        assert_eq!(gfx.1, 25.0);
+    }
+
+    #[test]
+    fn engine_map_counts_a_new_client_from_its_second_sample() {
+        let client = |id, gfx_ns, compute_ns| amdgpu_client(id, 4242, gfx_ns, compute_ns);
+        let mut usage = EngineUsage::default();
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        usage.update(&[client(1, 0, 0)], at(0));
+        let cards = usage.update(&[client(1, 500_000_000, 0), client(2, 9_000_000_000, 0)], at(1));
+        assert_eq!(cards["0000:03:00.0"], [("compute".to_string(), 0.0), ("gfx".to_string(), 50.0)]);
+
+        let cards = usage.update(&[client(1, 600_000_000, 0), client(2, 9_200_000_000, 300_000_000)], at(2));
+        assert_eq!(cards["0000:03:00.0"], [("compute".to_string(), 30.0), ("gfx".to_string(), 30.0)]);
+    }
+}
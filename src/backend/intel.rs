@@ -0,0 +1,173 @@
+//! Intel cards via i915/xe sysfs plus DRM fdinfo engine counters.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::metrics::{GpuMetrics, Vendor};
+use super::GpuBackend;
+use super::fdinfo::{DrmClient, EngineUsage, ProcessUsage, drm_clients, pci_slot};
+use super::sysfs::{DRM_ROOT, bytes_to_mb, drm_cards, first_hwmon, driver_version, hwmon_fans, pcie_link, read_num};
+
+const INTEL_VENDOR_ID: &str = "0x8086";
+
+/// Intel iGPUs and Arc cards on either the i915 or the xe driver.
+///
+/// Neither driver exposes a busy percentage in sysfs, so utilization comes from
+/// the per-client engine counters in `/proc/*/fdinfo` (what `intel_gpu_top`'s
+/// client view reads) and power from the hwmon energy counter. Both are deltas,
+/// so they read `None` on the first sample.
+pub struct IntelBackend {
+    prev: HashMap<String, IntelCounters>,
+    engines: EngineUsage,
+    processes: ProcessUsage,
+    /// i915 and xe can both be loaded, so one per module.
+    driver_versions: HashMap<String, Option<String>>,
+}
+
+/// Raw cumulative counters for one card, kept to diff against the next sample.
+pub struct IntelCounters {
+    at: Instant,
+    energy_uj: Option<u64>,
+}
+
+impl IntelBackend {
+    /// Fails if there's no i915/xe card at all, so callers can fall back to something else.
+    pub fn new() -> io::Result<Self> {
+        if intel_cards()?.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no Intel GPUs found under {DRM_ROOT}"),
+            ));
+        }
+        Ok(Self {
+            prev: HashMap::new(),
+            engines: EngineUsage::default(),
+            processes: ProcessUsage::default(),
+            driver_versions: HashMap::new(),
+        })
+    }
+}
+
+impl GpuBackend for IntelBackend {
+    fn name(&self) -> &str {
+        "Intel sysfs"
+    }
+
+    fn sample(&mut self) -> io::Result<Vec<GpuMetrics>> {
+        let cards = intel_cards()?;
+        let clients = drm_clients();
+        let now = Instant::now();
+        let mut engines = self.engines.update(&clients, now);
+        let mut processes = self.processes.update(&clients, now);
+        let mut out = Vec::with_capacity(cards.len());
+        let mut next = HashMap::with_capacity(cards.len());
+        for (card, device, driver) in cards {
+            let pdev = pci_slot(&device).unwrap_or_default();
+            let card_clients: Vec<&DrmClient> = clients.iter().filter(|c| c.pdev == pdev).collect();
+            let counters = IntelCounters {
+                at: now,
+                energy_uj: first_hwmon(&device).and_then(|h| read_num(&h.join("energy1_input"))),
+            };
+            let prev = self.prev.get(&card);
+            let mut gpu = read_intel_card(&card, &device, &driver, &card_clients, &counters, prev);
+            gpu.driver_version = self
+                .driver_versions
+                .entry(driver)
+                .or_insert_with_key(|driver| driver_version(driver))
+                .clone();
+            gpu.engine_utilization = engines.remove(&pdev).unwrap_or_default();
+            gpu.utilization_pct = gpu.engine_utilization.iter().map(|&(_, pct)| pct).reduce(f32::max);
+            gpu.processes = processes.remove(&pdev).unwrap_or_default();
+            out.push(gpu);
+            next.insert(card, counters);
+        }
+        // Rebuilt each time so unplugged cards don't linger
+        self.prev = next;
+        Ok(out)
+    }
+}
+
+/// `(cardN, device dir, driver name)` for every Intel card bound to i915 or xe.
+fn intel_cards() -> io::Result<Vec<(String, PathBuf, String)>> {
+    Ok(drm_cards(Path::new(DRM_ROOT), INTEL_VENDOR_ID)?
+        .into_iter()
+        .filter_map(|(card, device)| {
+            let driver = fs::read_link(device.join("driver")).ok()?;
+            let driver = driver.file_name()?.to_string_lossy().into_owned();
+            matches!(driver.as_str(), "i915" | "xe").then_some((card, device, driver))
+        })
+        .collect())
+}
+
+fn read_intel_card(
+    card: &str,
+    device: &Path,
+    driver: &str,
+    clients: &[&DrmClient],
+    now: &IntelCounters,
+    prev: Option<&IntelCounters>,
+) -> GpuMetrics {
+    let hwmon = first_hwmon(device);
+    let hw = |file: &str| hwmon.as_ref().map(|h| h.join(file));
+    let card_dir = Path::new(DRM_ROOT).join(card);
+
+    let core_clock_mhz = match driver {
+        "xe" => read_num(&device.join("tile0/gt0/freq0/act_freq")),
+        _ => read_num(&card_dir.join("gt_act_freq_mhz"))
+            .filter(|&mhz: &u32| mhz > 0)
+            .or_else(|| read_num(&card_dir.join("gt_cur_freq_mhz"))),
+    };
+    // RP0 is the hardware's max; gt_max_freq_mhz is a user-adjustable cap
+    let core_clock_max_mhz = match driver {
+        "xe" => read_num(&device.join("tile0/gt0/freq0/rp0_freq")),
+        _ => read_num(&card_dir.join("gt_RP0_freq_mhz")),
+    };
+
+    // Only discrete cards have local memory; iGPUs share system RAM, so leave it None
+    let vram_total_mb = match driver {
+        "xe" => read_num::<u64>(&device.join("tile0/physical_vram_size_bytes")),
+        _ => read_num::<u64>(&card_dir.join("lmem_total_bytes")),
+    }
+    .filter(|&b| b > 0)
+    .map(bytes_to_mb);
+    let vram_used_mb = vram_total_mb.and_then(|total| {
+        match read_num::<u64>(&card_dir.join("lmem_avail_bytes")) {
+            Some(avail) => Some(total.saturating_sub(bytes_to_mb(avail))),
+            None => {
+                let kib = clients.iter().filter_map(|c| c.vram_kib).reduce(|a, b| a + b);
+                kib.map(|k| (k / 1024) as u32)
+            }
+        }
+    });
+
+    let power_w = prev.and_then(|prev| {
+        let elapsed = now.at.duration_since(prev.at);
+        match (now.energy_uj, prev.energy_uj) {
+            (Some(e), Some(p)) if !elapsed.is_zero() => {
+                Some(e.saturating_sub(p) as f32 / 1_000_000.0 / elapsed.as_secs_f32())
+            }
+            _ => None,
+        }
+    });
+    let (pcie_gen, pcie_width) = pcie_link(device);
+
+    GpuMetrics {
+        name: format!("Intel GPU ({card}, {driver})"),
+        vendor: Vendor::Intel,
+        temperature_c: hw("temp1_input").and_then(|p| read_num::<f32>(&p)).map(|t| t / 1000.0),
+        vram_used_mb,
+        vram_total_mb,
+        power_w,
+        // PL1, microwatts
+        power_limit_w: hw("power1_max").and_then(|p| read_num::<f32>(&p)).map(|uw| uw / 1_000_000.0),
+        fans: hwmon.as_deref().map(hwmon_fans).unwrap_or_default(),
+        core_clock_mhz,
+        core_clock_max_mhz,
+        pcie_gen,
+        pcie_width,
+        ..Default::default()
+    }
+}
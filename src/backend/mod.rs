@@ -0,0 +1,149 @@
+//! Metrics sources and the auto-detection that picks one.
+
+mod amd;
+#[cfg(target_os = "macos")]
+mod apple;
+mod composite;
+mod fake;
+mod fdinfo;
+mod intel;
+mod nvml;
+pub mod remote;
+pub mod replay;
+mod rocm;
+mod sysfs;
+
+use std::io;
+use std::time::Instant;
+
+use anyhow::Context;
+use clap::ValueEnum;
+
+pub use amd::AmdSysfsBackend;
+pub use composite::CompositeBackend;
+pub use fake::FakeBackend;
+use intel::IntelBackend;
+use nvml::NvmlBackend;
+use rocm::RocmSmiBackend;
+use crate::metrics::GpuMetrics;
+
+pub use sysfs::describe_drm;
+
+/// Anything that can hand us a fresh set of GPU readings.
+///
+/// `&mut self` so implementations can keep handles/counters around between
+/// samples. `Send` because sampling runs on its own thread (see `Sampler`).
+pub trait GpuBackend: Send {
+    /// Short label for the header, e.g. "AMD sysfs".
+    fn name(&self) -> &str;
+
+    /// Only the mock makes its numbers up; the UI says so when it's active.
+    fn is_mock(&self) -> bool {
+        false
+    }
+
+    fn sample(&mut self) -> io::Result<Vec<GpuMetrics>>;
+
+    /// How each source is doing, for backends that stand for several (see
+    /// `CompositeBackend`). A single source has nothing to add to the stale
+    /// marker, hence empty by default.
+    fn health(&self) -> Vec<BackendHealth> {
+        vec![]
+    }
+}
+
+/// One source of a composed backend, for the footer's "NVML ✓ AMD sysfs ✗ 3s".
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackendHealth {
+    pub name: String,
+    /// Whether its latest sample came back.
+    pub ok: bool,
+    /// When one last did; `None` until the first success.
+    pub last_ok: Option<Instant>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BackendKind {
+    Amd,
+    /// AMD via `rocm-smi --json` (ROCm installs)
+    RocmSmi,
+    Nvidia,
+    Intel,
+    /// Apple Silicon integrated GPU (macOS only)
+    Apple,
+    Mock,
+}
+
+#[cfg(target_os = "macos")]
+fn open_apple() -> Option<Box<dyn GpuBackend>> {
+    apple::AppleBackend::new().ok().map(|b| Box::new(b) as Box<dyn GpuBackend>)
+}
+
+#[cfg(not(target_os = "macos"))]
+fn open_apple() -> Option<Box<dyn GpuBackend>> {
+    None
+}
+
+/// An explicitly requested backend must come up; auto-detect never fails (mock is the floor).
+pub fn open_backend(kind: Option<BackendKind>) -> anyhow::Result<Box<dyn GpuBackend>> {
+    Ok(match kind {
+        Some(BackendKind::Amd) => {
+            Box::new(AmdSysfsBackend::new().context("failed to initialize AMD sysfs backend")?)
+        }
+        Some(BackendKind::RocmSmi) => {
+            Box::new(RocmSmiBackend::new().context("failed to initialize rocm-smi backend")?)
+        }
+        Some(BackendKind::Nvidia) => {
+            Box::new(NvmlBackend::new().context("failed to initialize NVML backend")?)
+        }
+        Some(BackendKind::Intel) => {
+            Box::new(IntelBackend::new().context("failed to initialize Intel sysfs backend")?)
+        }
+        #[cfg(target_os = "macos")]
+        Some(BackendKind::Apple) => {
+            Box::new(apple::AppleBackend::new().context("failed to initialize IOReport backend")?)
+        }
+        #[cfg(not(target_os = "macos"))]
+        Some(BackendKind::Apple) => anyhow::bail!("the Apple backend is only available on macOS"),
+        Some(BackendKind::Mock) => Box::new(FakeBackend::new()),
+        None => {
+            // One per vendor that has cards here; rocm-smi and AMD sysfs see
+            // the same ones, so rocm-smi only when sysfs finds none
+            let mut found: Vec<Box<dyn GpuBackend>> = vec![];
+            if let Ok(nvml) = NvmlBackend::new() {
+                found.push(Box::new(nvml));
+            }
+            if let Ok(amd) = AmdSysfsBackend::new() {
+                found.push(Box::new(amd));
+            } else if let Ok(mut rocm) = RocmSmiBackend::new()
+                && rocm.sample().is_ok_and(|gpus| !gpus.is_empty())
+            {
+                // A process per sample is too slow to prefer; it's for cards
+                // sysfs doesn't show us (containers without /sys/class/drm)
+                found.push(Box::new(rocm));
+            }
+            if let Ok(intel) = IntelBackend::new() {
+                found.push(Box::new(intel));
+            }
+            found.extend(open_apple());
+            match found.len() {
+                0 => Box::new(FakeBackend::new()),
+                1 => found.remove(0),
+                _ => Box::new(CompositeBackend::new(found)),
+            }
+        }
+    })
+}
+
+/// `--backend nvidia,amd`: each must come up, and together they act as one.
+/// None or one kind is just `open_backend`.
+pub fn open_backends(kinds: &[BackendKind]) -> anyhow::Result<Box<dyn GpuBackend>> {
+    match kinds {
+        [] => open_backend(None),
+        [kind] => open_backend(Some(*kind)),
+        _ => {
+            let backends = kinds.iter().map(|&k| open_backend(Some(k))).collect::<anyhow::Result<_>>()?;
+            Ok(Box::new(CompositeBackend::new(backends)))
+        }
+    }
+}
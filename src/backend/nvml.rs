@@ -0,0 +1,182 @@
+//! NVIDIA cards via NVML.
+
+use std::io;
+
+use nvml_wrapper::{
+    bitmasks::device::ThrottleReasons,
+    enum_wrappers::device::{Clock, EccCounter, MemoryError, PerformanceState, TemperatureSensor},
+    enums::device::{SampleValue, UsedGpuMemory},
+    structs::device::FieldId,
+    sys_exports::field_id::{NVML_FI_DEV_MEMORY_TEMP, NVML_FI_DEV_POWER_INSTANT},
+    Device, Nvml,
+};
+
+use crate::metrics::{FanMetrics, GpuMetrics, GpuProcess, Vendor};
+use super::GpuBackend;
+use super::sysfs::{bytes_to_mb, process_name};
+
+/// NVIDIA cards through NVML (libnvidia-ml is loaded at runtime, not linked).
+pub struct NvmlBackend {
+    nvml: Nvml,
+    driver_version: Option<String>,
+    /// Per device index, filled in the first time each device is sampled.
+    vbios: Vec<Option<String>>,
+}
+
+impl NvmlBackend {
+    /// Fails when there's no driver/library or no devices, so callers can fall back.
+    pub fn new() -> io::Result<Self> {
+        let nvml = Nvml::init().map_err(io::Error::other)?;
+        if nvml.device_count().map_err(io::Error::other)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "NVML reports no devices"));
+        }
+        let driver_version = nvml.sys_driver_version().ok();
+        Ok(Self { nvml, driver_version, vbios: vec![] })
+    }
+}
+
+impl GpuBackend for NvmlBackend {
+    fn name(&self) -> &str {
+        "NVML"
+    }
+
+    fn sample(&mut self) -> io::Result<Vec<GpuMetrics>> {
+        let count = self.nvml.device_count().map_err(io::Error::other)?;
+        let mut out = Vec::with_capacity(count as usize);
+        for i in 0..count {
+            let device = self.nvml.device_by_index(i).map_err(io::Error::other)?;
+            let mut gpu = read_nvml_device(i, &device);
+            if self.vbios.len() <= i as usize {
+                self.vbios.push(device.vbios_version().ok());
+            }
+            gpu.driver_version = self.driver_version.clone();
+            gpu.vbios_version = self.vbios[i as usize].clone();
+            out.push(gpu);
+        }
+        Ok(out)
+    }
+}
+
+fn read_nvml_device(index: u32, device: &Device) -> GpuMetrics {
+    let memory = device.memory_info().ok();
+    let rates = device.utilization_rates().ok();
+
+    GpuMetrics {
+        name: device.name().unwrap_or_else(|_| format!("NVIDIA GPU {index}")),
+        vendor: Vendor::Nvidia,
+        temperature_c: device.temperature(TemperatureSensor::Gpu).ok().map(|t| t as f32),
+        // NVML has no public hotspot sensor
+        junction_temp_c: None,
+        mem_temp_c: nvml_mem_temp(device),
+        utilization_pct: rates.as_ref().map(|u| u.gpu as f32),
+        mem_utilization_pct: rates.as_ref().map(|u| u.memory as f32),
+        engine_utilization: nvml_engines(device, rates.as_ref().map(|u| u.gpu)),
+        vram_used_mb: memory.as_ref().map(|m| bytes_to_mb(m.used)),
+        vram_total_mb: memory.as_ref().map(|m| bytes_to_mb(m.total)),
+        // milliwatts
+        power_w: device.power_usage().ok().map(|mw| mw as f32 / 1000.0),
+        // Without a scope id the field reads the GPU scope, not the whole module
+        gpu_chip_power_w: nvml_field(device, NVML_FI_DEV_POWER_INSTANT).map(|mw| mw as f32 / 1000.0),
+        power_limit_w: device.power_management_limit().ok().map(|mw| mw as f32 / 1000.0),
+        fans: nvml_fans(device),
+        core_clock_mhz: device.clock_info(Clock::Graphics).ok(),
+        mem_clock_mhz: device.clock_info(Clock::Memory).ok(),
+        core_clock_max_mhz: device.max_clock_info(Clock::Graphics).ok(),
+        mem_clock_max_mhz: device.max_clock_info(Clock::Memory).ok(),
+        perf_state: match device.performance_state() {
+            Ok(PerformanceState::Unknown) | Err(_) => None,
+            Ok(state) => Some(format!("P{}", state.as_c())),
+        },
+        ecc_errors: nvml_ecc_errors(device),
+        pcie_gen: device.current_pcie_link_gen().ok().map(|g| g as u8),
+        pcie_width: device.current_pcie_link_width().ok().map(|w| w as u8),
+        throttle_reasons: nvml_throttle_reasons(device),
+        processes: nvml_processes(device),
+        ..Default::default()
+    }
+}
+
+/// The video engines next to the graphics/compute figure, which leaves
+/// them out; nothing on cards (or vGPUs) without NVENC/NVDEC.
+fn nvml_engines(device: &Device, gpu: Option<u32>) -> Vec<(String, f32)> {
+    let video = [("encoder", device.encoder_utilization()), ("decoder", device.decoder_utilization())];
+    let video: Vec<(String, f32)> = video
+        .into_iter()
+        .filter_map(|(name, info)| Some((name.to_string(), info.ok()?.utilization as f32)))
+        .collect();
+    if video.is_empty() {
+        return vec![];
+    }
+    gpu.map(|g| ("graphics".to_string(), g as f32)).into_iter().chain(video).collect()
+}
+
+/// Only with ECC switched on: with it off NVML still answers, with stale counts.
+fn nvml_ecc_errors(device: &Device) -> Option<u64> {
+    device.is_ecc_enabled().ok().filter(|mode| mode.currently_enabled)?;
+    device.total_ecc_errors(MemoryError::Uncorrected, EccCounter::Volatile).ok()
+}
+
+fn nvml_fans(device: &Device) -> Vec<FanMetrics> {
+    (0..device.num_fans().unwrap_or(0))
+        .map(|i| FanMetrics {
+            index: i,
+            rpm: device.fan_speed_rpm(i).ok(),
+            // NVML's fan_speed is already a percentage of max
+            pct: device.fan_speed(i).ok().map(|p| p as f32),
+        })
+        .collect()
+}
+
+/// Idle isn't throttling in any useful sense, so it's left out.
+fn nvml_throttle_reasons(device: &Device) -> Vec<String> {
+    let Ok(reasons) = device.current_throttle_reasons() else {
+        return vec![];
+    };
+    let labels = [
+        (ThrottleReasons::SW_THERMAL_SLOWDOWN | ThrottleReasons::HW_THERMAL_SLOWDOWN, "thermal"),
+        (ThrottleReasons::SW_POWER_CAP, "power"),
+        (ThrottleReasons::HW_POWER_BRAKE_SLOWDOWN, "power brake"),
+        (ThrottleReasons::HW_SLOWDOWN, "hw slowdown"),
+        (ThrottleReasons::SYNC_BOOST, "sync boost"),
+        (ThrottleReasons::APPLICATIONS_CLOCKS_SETTING, "clock limit"),
+        (ThrottleReasons::DISPLAY_CLOCK_SETTING, "display clock"),
+    ];
+    labels
+        .into_iter()
+        .filter(|(bits, _)| reasons.intersects(*bits))
+        .map(|(_, label)| label.to_string())
+        .collect()
+}
+
+fn nvml_processes(device: &Device) -> Vec<GpuProcess> {
+    device
+        .running_compute_processes()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|p| GpuProcess {
+            pid: p.pid,
+            name: process_name(p.pid).unwrap_or_else(|| "?".into()),
+            used_mb: match p.used_gpu_memory {
+                UsedGpuMemory::Used(bytes) => Some(bytes_to_mb(bytes)),
+                UsedGpuMemory::Unavailable => None,
+            },
+            util_pct: None,
+        })
+        .collect()
+}
+
+/// Memory temp is only reachable through the field-values API (HBM/GDDR6X cards mostly).
+fn nvml_mem_temp(device: &Device) -> Option<f32> {
+    nvml_field(device, NVML_FI_DEV_MEMORY_TEMP).map(|t| t as f32)
+}
+
+/// One field-values API reading, `None` if the driver or card doesn't have it.
+fn nvml_field(device: &Device, id: u32) -> Option<f64> {
+    let sample = device.field_values_for(&[FieldId(id)]).ok()?.into_iter().next()?.ok()?;
+    match sample.value.ok()? {
+        SampleValue::U32(v) => Some(v as f64),
+        SampleValue::U64(v) => Some(v as f64),
+        SampleValue::I64(v) => Some(v as f64),
+        SampleValue::F64(v) => Some(v),
+    }
+}
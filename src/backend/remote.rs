@@ -0,0 +1,206 @@
+//! `--connect`: readings from another machine's `gtop --daemon`, or a local
+//! `gtop --socket`.
+//!
+//! The protocol is request/response over one connection: the client sends
+//! a single byte, the daemon answers with the latest sample as a big-endian
+//! `u32` length followed by that many bytes of JSON (`Vec<GpuMetrics>`).
+
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::metrics::GpuMetrics;
+use super::GpuBackend;
+
+/// Anything bigger is a confused peer, not a sample.
+const MAX_FRAME: u32 = 16 * 1024 * 1024;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+/// Past this a daemon counts as gone and we reconnect on the next sample.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn write_frame(out: &mut impl Write, metrics: &[GpuMetrics]) -> io::Result<()> {
+    let body = serde_json::to_vec(metrics)?;
+    let len = u32::try_from(body.len()).ok().filter(|&n| n <= MAX_FRAME);
+    let len = len.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "sample too large to send"))?;
+    out.write_all(&len.to_be_bytes())?;
+    out.write_all(&body)?;
+    out.flush()
+}
+
+pub fn read_frame(input: &mut (impl Read + ?Sized)) -> io::Result<Vec<GpuMetrics>> {
+    let mut len = [0; 4];
+    input.read_exact(&mut len)?;
+    let len = u32::from_be_bytes(len);
+    if len > MAX_FRAME {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("frame of {len} bytes")));
+    }
+    let mut body = vec![0; len as usize];
+    input.read_exact(&mut body)?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+trait Connection: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Connection for T {}
+
+enum Target {
+    Tcp(String),
+    Unix(PathBuf),
+}
+
+/// Client end. A failed request drops the connection; the next `sample`
+/// dials again, so a daemon restart only shows up as a few stale ticks.
+pub struct RemoteBackend {
+    target: Target,
+    label: String,
+    stream: Option<Box<dyn Connection>>,
+}
+
+impl RemoteBackend {
+    /// Doesn't connect yet: the daemon is allowed to come up after we do.
+    pub fn new(addr: &str) -> Self {
+        Self { target: Target::Tcp(addr.to_string()), label: format!("remote {addr}"), stream: None }
+    }
+
+    /// A `gtop --socket` on this machine.
+    pub fn unix(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self { label: format!("socket {}", path.display()), target: Target::Unix(path), stream: None }
+    }
+
+    fn connect(&self) -> io::Result<Box<dyn Connection>> {
+        match &self.target {
+            Target::Tcp(addr) => Ok(Box::new(connect_tcp(addr)?)),
+            Target::Unix(path) => connect_unix(path),
+        }
+    }
+
+    fn request(stream: &mut dyn Connection) -> io::Result<Vec<GpuMetrics>> {
+        stream.write_all(&[0])?;
+        read_frame(stream)
+    }
+}
+
+fn connect_tcp(addr: &str) -> io::Result<TcpStream> {
+    let mut last = io::Error::new(io::ErrorKind::NotFound, format!("{addr} did not resolve"));
+    for addr in addr.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+            Ok(stream) => {
+                stream.set_read_timeout(Some(READ_TIMEOUT))?;
+                stream.set_nodelay(true)?;
+                return Ok(stream);
+            }
+            Err(e) => last = e,
+        }
+    }
+    Err(last)
+}
+
+#[cfg(unix)]
+fn connect_unix(path: &std::path::Path) -> io::Result<Box<dyn Connection>> {
+    let stream = std::os::unix::net::UnixStream::connect(path)?;
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    Ok(Box::new(stream))
+}
+
+#[cfg(not(unix))]
+fn connect_unix(path: &std::path::Path) -> io::Result<Box<dyn Connection>> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, format!("{}: Unix sockets need a Unix system", path.display())))
+}
+
+impl GpuBackend for RemoteBackend {
+    fn name(&self) -> &str {
+        &self.label
+    }
+
+    fn sample(&mut self) -> io::Result<Vec<GpuMetrics>> {
+        let mut stream = match self.stream.take() {
+            Some(stream) => stream,
+            None => self.connect()?,
+        };
+        let mut metrics = Self::request(stream.as_mut())?;
+        self.stream = Some(stream);
+        // The daemon's clock needn't agree with ours; staleness is about when
+        // a frame got here, and a daemon that stops answering still shows
+        for gpu in &mut metrics {
+            gpu.timestamp = Instant::now();
+        }
+        Ok(metrics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_round_trip() {
+        let gpu = GpuMetrics { name: "Remote GPU".into(), power_w: Some(42.0), ..Default::default() };
+        let mut wire = vec![];
+        write_frame(&mut wire, &[gpu]).unwrap();
+        assert_eq!(u32::from_be_bytes(wire[..4].try_into().unwrap()) as usize, wire.len() - 4);
+
+        let back = read_frame(&mut wire.as_slice()).unwrap();
+        assert_eq!(back.len(), 1);
+        assert_eq!((back[0].name.as_str(), back[0].power_w), ("Remote GPU", Some(42.0)));
+    }
+
+    /// A daemon's answer already on the wire; whatever the client sends is dropped.
+    struct Canned(io::Cursor<Vec<u8>>);
+
+    impl Read for Canned {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.0.read(buf)
+        }
+    }
+
+    impl Write for Canned {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn samples_are_stamped_on_arrival_not_by_the_daemon_clock() {
+        // A daemon whose clock runs half a minute behind ours
+        let mut gpu = serde_json::to_value(GpuMetrics { name: "Remote GPU".into(), ..Default::default() }).unwrap();
+        let behind = gpu["timestamp_ms"].as_u64().unwrap() - 30_000;
+        gpu["timestamp_ms"] = behind.into();
+        let body = serde_json::to_vec(&[gpu]).unwrap();
+        let mut wire = (body.len() as u32).to_be_bytes().to_vec();
+        wire.extend(body);
+
+        let mut client = RemoteBackend::new("daemon:7878");
+        client.stream = Some(Box::new(Canned(io::Cursor::new(wire))));
+        let metrics = client.sample().unwrap();
+        assert!(metrics[0].timestamp.elapsed() < Duration::from_secs(1));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn socket_daemon_serves_replaces_stale_sockets_and_cleans_up() {
+        use crate::server::{SharedMetrics, spawn_socket_daemon};
+
+        let path = std::env::temp_dir().join(format!("gtop-test-{}.sock", std::process::id()));
+        // What a killed gtop leaves behind: the file, with nobody listening
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+
+        let shared = SharedMetrics::default();
+        shared.lock().unwrap().push(GpuMetrics { name: "Local GPU".into(), ..Default::default() });
+        let file = spawn_socket_daemon(&path, shared.clone()).unwrap();
+        let err = spawn_socket_daemon(&path, shared).unwrap_err();
+        assert!(err.to_string().contains("in use by another running gtop"), "{err}");
+
+        let mut client = RemoteBackend::unix(&path);
+        assert_eq!(client.name(), format!("socket {}", path.display()));
+        assert_eq!(client.sample().unwrap()[0].name, "Local GPU");
+
+        drop(file);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,154 @@
+//! `--record` writer and the `--replay` backend that plays recordings back.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::metrics::GpuMetrics;
+use super::GpuBackend;
+
+/// One line of a `--record` file: a full sample plus when it was taken,
+/// relative to the first sample of the recording.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordedSample {
+    t_ms: u64,
+    metrics: Vec<GpuMetrics>,
+}
+
+/// `--record` writer, JSON lines of `RecordedSample`.
+pub struct Recorder {
+    out: BufWriter<File>,
+    start: Option<Instant>,
+}
+
+impl Recorder {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Ok(Self { out: BufWriter::new(File::create(path)?), start: None })
+    }
+
+    pub fn record(&mut self, metrics: &[GpuMetrics]) -> io::Result<()> {
+        let start = *self.start.get_or_insert_with(Instant::now);
+        let sample = RecordedSample {
+            t_ms: start.elapsed().as_millis() as u64,
+            metrics: metrics.to_vec(),
+        };
+        serde_json::to_writer(&mut self.out, &sample)?;
+        writeln!(self.out)?;
+        // Recordings are small and losing the tail on Ctrl-C defeats the point
+        self.out.flush()
+    }
+}
+
+/// Plays a `--record` file back. The sample handed out is whichever one was
+/// current at the same offset into the original session, so playback keeps the
+/// recorded cadence no matter what `--interval` is. Loops at the end.
+pub struct ReplayBackend {
+    /// In `t_ms` order; `open` refuses recordings that go back in time.
+    samples: Vec<RecordedSample>,
+    /// Length of one pass: first to last sample, plus the last one held for
+    /// as long as a typical sample was.
+    span_ms: u64,
+    start: Instant,
+    /// "replay of <file name>", for the header.
+    label: String,
+}
+
+impl ReplayBackend {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut samples = vec![];
+        for (n, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let sample: RecordedSample = serde_json::from_str(&line).map_err(|e| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {e}", n + 1))
+            })?;
+            // Two recordings joined end to end, say; there's no single timeline to play
+            if let Some(prev) = samples.last().map(|s: &RecordedSample| s.t_ms)
+                && sample.t_ms < prev
+            {
+                let msg = format!("line {}: t_ms goes back from {prev} to {}", n + 1, sample.t_ms);
+                return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+            }
+            samples.push(sample);
+        }
+        if samples.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "recording has no samples"));
+        }
+        let mut gaps: Vec<u64> = samples.windows(2).map(|w| w[1].t_ms - w[0].t_ms).collect();
+        gaps.sort_unstable();
+        let hold = gaps.get(gaps.len() / 2).copied().unwrap_or(0).max(1);
+        let span_ms = samples[samples.len() - 1].t_ms - samples[0].t_ms + hold;
+        let file = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy();
+        Ok(Self { samples, span_ms, start: Instant::now(), label: format!("replay of {file}") })
+    }
+}
+
+impl GpuBackend for ReplayBackend {
+    fn name(&self) -> &str {
+        &self.label
+    }
+
+    fn sample(&mut self) -> io::Result<Vec<GpuMetrics>> {
+        let offset = self.samples[0].t_ms + self.start.elapsed().as_millis() as u64 % self.span_ms;
+
+        let idx = self.samples.partition_point(|s| s.t_ms <= offset).saturating_sub(1);
+        let mut metrics = self.samples[idx].metrics.clone();
+        for gpu in &mut metrics {
+            gpu.timestamp = Instant::now();
+        }
+        Ok(metrics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::time::Duration;
+
+    use super::*;
+
+    fn gpu(name: &str, power_w: f32) -> GpuMetrics {
+        GpuMetrics { name: name.into(), power_w: Some(power_w), ..Default::default() }
+    }
+
+    #[test]
+    fn recordings_play_back_at_their_own_cadence() {
+        let path = std::env::temp_dir().join(format!("gtop-replay-{}.jsonl", std::process::id()));
+        let mut rec = Recorder::create(&path).unwrap();
+        // A second apart, without waiting for it
+        for (i, name) in ["a", "b", "c"].into_iter().enumerate() {
+            rec.start = Some(Instant::now() - Duration::from_millis(1000 * i as u64));
+            rec.record(&[gpu(name, 100.0 + i as f32)]).unwrap();
+        }
+        drop(rec);
+        let mut replay = ReplayBackend::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let mut at = |ms| {
+            replay.start = Instant::now() - Duration::from_millis(ms);
+            let gpus = replay.sample().unwrap();
+            (gpus[0].name.clone(), gpus[0].power_w)
+        };
+        assert_eq!(at(500), ("a".to_string(), Some(100.0)));
+        assert_eq!(at(1500), ("b".to_string(), Some(101.0)));
+        // The last sample gets its second too before it loops
+        assert_eq!(at(2700), ("c".to_string(), Some(102.0)));
+        assert_eq!(at(3200), ("a".to_string(), Some(100.0)));
+    }
+
+    #[test]
+    fn recordings_that_go_back_in_time_are_refused() {
+        let path = std::env::temp_dir().join(format!("gtop-replay-back-{}.jsonl", std::process::id()));
+        fs::write(&path, "{\"t_ms\":500,\"metrics\":[]}\n{\"t_ms\":0,\"metrics\":[]}\n").unwrap();
+        let err = ReplayBackend::open(&path).err().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("line 2:"), "{err}");
+    }
+}
@@ -0,0 +1,157 @@
+//! AMD cards via `rocm-smi --json`, for ROCm installs (MI-series especially)
+//! where the tool knows more than the sysfs nodes we read directly.
+
+use std::io;
+use std::process::Command;
+
+use serde_json::{Map, Value};
+
+use crate::metrics::{FanMetrics, GpuMetrics, Vendor};
+use super::GpuBackend;
+use super::sysfs::bytes_to_mb;
+
+const ROCM_SMI: &str = "rocm-smi";
+const ROCM_SMI_ARGS: &[&str] = &["--showallinfo", "--showmeminfo", "vram", "--json"];
+
+/// Shells out to `rocm-smi` once per sample. Slow (a few hundred ms), but
+/// sampling has its own thread, so that only limits how fresh readings get.
+pub struct RocmSmiBackend;
+
+impl RocmSmiBackend {
+    /// Fails if `rocm-smi` isn't installed (or won't run), so callers can
+    /// fall back to something else. Only asks for the version: a full
+    /// sample is slow enough to be worth saving for the first tick.
+    pub fn new() -> io::Result<Self> {
+        let status = Command::new(ROCM_SMI).arg("--version").output().map_err(not_on_path)?.status;
+        if !status.success() {
+            return Err(io::Error::other(format!("rocm-smi --version failed ({status})")));
+        }
+        Ok(Self)
+    }
+}
+
+fn not_on_path(e: io::Error) -> io::Error {
+    match e.kind() {
+        io::ErrorKind::NotFound => io::Error::new(e.kind(), "rocm-smi is not on PATH"),
+        _ => e,
+    }
+}
+
+impl GpuBackend for RocmSmiBackend {
+    fn name(&self) -> &str {
+        "rocm-smi"
+    }
+
+    fn sample(&mut self) -> io::Result<Vec<GpuMetrics>> {
+        let output = Command::new(ROCM_SMI).args(ROCM_SMI_ARGS).output().map_err(not_on_path)?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let reason = stderr.lines().rfind(|l| !l.trim().is_empty()).unwrap_or("no output");
+            return Err(io::Error::other(format!("rocm-smi failed ({}): {}", output.status, reason.trim())));
+        }
+        parse_rocm_smi(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+/// `{"card0": {"GPU use (%)": "12", ...}, "system": {...}}`: every value is a
+/// string, and key spellings differ between ROCm releases, hence the
+/// candidate lists. Anything missing or "N/A" becomes `None`.
+pub fn parse_rocm_smi(text: &str) -> io::Result<Vec<GpuMetrics>> {
+    // Some releases print warnings ahead of the JSON
+    let json = text.find('{').map_or(text, |start| &text[start..]);
+    let root: Map<String, Value> = serde_json::from_str(json)?;
+    let driver = root.get("system").and_then(Value::as_object).and_then(|s| text_field(s, &["Driver version"]));
+
+    let mut cards: Vec<(u32, &Map<String, Value>)> = root
+        .iter()
+        .filter_map(|(key, value)| Some((key.strip_prefix("card")?.parse().ok()?, value.as_object()?)))
+        .collect();
+    cards.sort_by_key(|&(n, _)| n);
+
+    Ok(cards
+        .into_iter()
+        .map(|(n, card)| {
+            let num = |keys: &[&str]| text_field(card, keys).and_then(|v| leading_number(&v));
+            let bytes = |keys: &[&str]| text_field(card, keys)?.parse().ok().map(bytes_to_mb);
+            let mhz = |keys: &[&str]| num(keys).map(|m| m as u32);
+            let fan_pct = num(&["Fan speed (%)"]);
+            let fan_rpm = num(&["Fan RPM"]).map(|r| r as u32);
+            GpuMetrics {
+                name: text_field(card, &["Card Series", "Card series", "Device Name", "Card model"])
+                    .unwrap_or_else(|| format!("AMD GPU (card{n})")),
+                vendor: Vendor::Amd,
+                temperature_c: num(&["Temperature (Sensor edge) (C)"]),
+                junction_temp_c: num(&["Temperature (Sensor junction) (C)"]),
+                mem_temp_c: num(&["Temperature (Sensor memory) (C)"]),
+                utilization_pct: num(&["GPU use (%)"]),
+                mem_utilization_pct: num(&["GPU Memory Read/Write Activity (%)", "GPU memory use (%)"]),
+                vram_used_mb: bytes(&["VRAM Total Used Memory (B)"]),
+                vram_total_mb: bytes(&["VRAM Total Memory (B)"]),
+                power_w: num(&[
+                    "Current Socket Graphics Package Power (W)",
+                    "Average Graphics Package Power (W)",
+                ]),
+                power_limit_w: num(&["Max Graphics Package Power (W)"]),
+                fans: if fan_pct.is_some() || fan_rpm.is_some() {
+                    vec![FanMetrics { index: 0, rpm: fan_rpm, pct: fan_pct }]
+                } else {
+                    vec![]
+                },
+                core_clock_mhz: mhz(&["sclk clock speed:"]),
+                mem_clock_mhz: mhz(&["mclk clock speed:"]),
+                perf_state: text_field(card, &["Performance Level"]),
+                driver_version: driver.clone().or_else(|| text_field(card, &["Driver version"])),
+                vbios_version: text_field(card, &["VBIOS version"]),
+                ..Default::default()
+            }
+        })
+        .collect())
+}
+
+/// First of `keys` present with a real value.
+fn text_field(obj: &Map<String, Value>, keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .filter_map(|k| obj.get(*k)?.as_str())
+        .map(str::trim)
+        .find(|v| !v.is_empty() && *v != "N/A")
+        .map(str::to_string)
+}
+
+/// "45.0" -> 45.0, "(1800Mhz)" -> 1800.0.
+fn leading_number(text: &str) -> Option<f32> {
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let rest = &text[start..];
+    let end = rest.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(rest.len());
+    rest[..end].parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cards_in_order_and_skips_missing_values() {
+        let json = r#"WARNING: some ROCm notice
+{
+  "card1": {"Card Series": "AMD Instinct MI210", "GPU use (%)": "97",
+            "Temperature (Sensor junction) (C)": "78.0", "Fan speed (%)": "N/A",
+            "VRAM Total Memory (B)": "68702699520", "VRAM Total Used Memory (B)": "1073741824",
+            "Current Socket Graphics Package Power (W)": "N/A",
+            "Average Graphics Package Power (W)": "291.0", "sclk clock speed:": "(1700Mhz)"},
+  "card0": {"GPU use (%)": "0", "Fan RPM": "1200"},
+  "system": {"Driver version": "6.7.0"}
+}"#;
+        let gpus = parse_rocm_smi(json).unwrap();
+        assert_eq!(gpus.len(), 2);
+        assert_eq!(gpus[0].name, "AMD GPU (card0)");
+        assert_eq!(gpus[0].fan_rpm(), Some(1200));
+
+        let mi = &gpus[1];
+        assert_eq!(mi.name, "AMD Instinct MI210");
+        assert_eq!((mi.utilization_pct, mi.junction_temp_c, mi.power_w), (Some(97.0), Some(78.0), Some(291.0)));
+        assert_eq!((mi.vram_used_mb, mi.vram_total_mb), (Some(1024), Some(65520)));
+        assert_eq!(mi.core_clock_mhz, Some(1700));
+        assert!(mi.fans.is_empty() && mi.temperature_c.is_none());
+        assert_eq!(mi.driver_version.as_deref(), Some("6.7.0"));
+    }
+}
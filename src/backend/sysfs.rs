@@ -0,0 +1,227 @@
+//! Small helpers for reading `/sys/class/drm` shared by the sysfs backends.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::metrics::{FanControl, FanMetrics};
+
+pub const DRM_ROOT: &str = "/sys/class/drm";
+
+/// `(cardN, <root>/cardN/device)` for every card from `vendor`, ordered by N.
+/// `root` is `DRM_ROOT` outside of fixtures.
+pub fn drm_cards(root: &Path, vendor: &str) -> io::Result<Vec<(String, PathBuf)>> {
+    let mut cards = all_drm_cards(root)?;
+    cards.retain(|(_, device)| read_trimmed(&device.join("vendor")).as_deref() == Some(vendor));
+    Ok(cards)
+}
+
+/// Like `drm_cards`, whatever the vendor.
+fn all_drm_cards(root: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+    let mut cards = vec![];
+    for entry in fs::read_dir(root)? {
+        let entry = entry?;
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        // cardN only, not the connectors (card0-DP-1 etc.)
+        let Some(idx) = name.strip_prefix("card").and_then(|n| n.parse::<u32>().ok()) else {
+            continue;
+        };
+        cards.push((idx, name, entry.path().join("device")));
+    }
+    cards.sort_by_key(|(idx, _, _)| *idx);
+    Ok(cards.into_iter().map(|(_, name, device)| (name, device)).collect())
+}
+
+/// Device and hwmon nodes the sysfs backends read, listed by `--doctor`.
+const DOCTOR_DEVICE_FILES: &[&str] = &[
+    "vendor",
+    "product_name",
+    "gpu_busy_percent",
+    "mem_busy_percent",
+    "ras/umc_err_count",
+    "mem_info_vram_used",
+    "mem_info_vram_total",
+    "pp_dpm_sclk",
+    "pp_dpm_mclk",
+    "power_dpm_force_performance_level",
+    "current_link_speed",
+    "current_link_width",
+    "vbios_version",
+];
+const DOCTOR_HWMON_FILES: &[&str] = &[
+    "temp1_input",
+    "temp2_input",
+    "temp3_input",
+    "power1_average",
+    "power1_input",
+    "power1_cap",
+    "power1_max",
+    "energy1_input",
+    "fan1_input",
+    "pwm1",
+    "pwm1_enable",
+    "freq1_input",
+    "freq2_input",
+];
+
+/// `--doctor`: every DRM card with its driver and what each node we care
+/// about reads as (value, absent, or the error), whichever backend owns it.
+pub fn describe_drm(out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "DRM cards under {DRM_ROOT}:")?;
+    let cards = match all_drm_cards(Path::new(DRM_ROOT)) {
+        Ok(cards) => cards,
+        Err(e) => return writeln!(out, "  can't list: {e}"),
+    };
+    if cards.is_empty() {
+        writeln!(out, "  none")?;
+    }
+    for (card, device) in cards {
+        let driver = fs::read_link(device.join("driver"))
+            .ok()
+            .and_then(|d| Some(d.file_name()?.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| "no driver bound".into());
+        writeln!(out, "  {card} ({driver})")?;
+        describe_files(out, &device, DOCTOR_DEVICE_FILES, 4)?;
+        match first_hwmon(&device) {
+            Some(hwmon) => {
+                writeln!(out, "    {}:", hwmon.display())?;
+                describe_files(out, &hwmon, DOCTOR_HWMON_FILES, 6)?;
+            }
+            None => writeln!(out, "    no hwmon directory")?,
+        }
+    }
+    Ok(())
+}
+
+fn describe_files(out: &mut dyn Write, dir: &Path, files: &[&str], indent: usize) -> io::Result<()> {
+    for file in files {
+        let status = match fs::read_to_string(dir.join(file)) {
+            // Only the first line: pp_dpm_* tables go on for a while
+            Ok(text) => text.lines().next().unwrap_or("").trim().to_string(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => format!("error: {e}"),
+        };
+        writeln!(out, "{:indent$}{file:<20} {status}", "")?;
+    }
+    Ok(())
+}
+
+/// `device/hwmon/hwmonN` — amdgpu only registers one per card.
+pub fn first_hwmon(device: &Path) -> Option<PathBuf> {
+    fs::read_dir(device.join("hwmon"))
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .find(|p| p.file_name().is_some_and(|n| n.to_string_lossy().starts_with("hwmon")))
+}
+
+/// The PCI device's negotiated link as `(generation, width)`, from
+/// `current_link_speed` ("16.0 GT/s PCIe") and `current_link_width` ("16").
+/// Integrated GPUs have neither.
+pub fn pcie_link(device: &Path) -> (Option<u8>, Option<u8>) {
+    let generation = read_trimmed(&device.join("current_link_speed")).and_then(|s| pcie_gen(&s));
+    (generation, read_num(&device.join("current_link_width")))
+}
+
+fn pcie_gen(speed: &str) -> Option<u8> {
+    let gts: f32 = speed.split_whitespace().next()?.parse().ok()?;
+    // Per-lane transfer rate doubles every generation from 8 GT/s on
+    [(2.5, 1), (5.0, 2), (8.0, 3), (16.0, 4), (32.0, 5), (64.0, 6)]
+        .into_iter()
+        .find(|&(rate, _)| (gts - rate).abs() < 0.1)
+        .map(|(_, generation)| generation)
+}
+
+/// `/sys/module/<module>/version` only exists for out-of-tree (DKMS) builds;
+/// an in-tree driver is versioned with the kernel.
+pub fn driver_version(module: &str) -> Option<String> {
+    read_trimmed(&Path::new("/sys/module").join(module).join("version")).or_else(|| {
+        let release = read_trimmed(Path::new("/proc/sys/kernel/osrelease"))?;
+        Some(format!("{module} (kernel {release})"))
+    })
+}
+
+/// `fanN_input` / `pwmN` pairs from N = 1 up to the first fan with neither.
+pub fn hwmon_fans(hwmon: &Path) -> Vec<FanMetrics> {
+    (1..)
+        .map_while(|n| {
+            let rpm = read_num::<u32>(&hwmon.join(format!("fan{n}_input")));
+            let pct = hwmon_fan_pct(hwmon, n, rpm);
+            (rpm.is_some() || pct.is_some()).then_some(FanMetrics { index: n - 1, rpm, pct })
+        })
+        .collect()
+}
+
+/// Who drives the first fan. Multi-fan cards share one PWM controller.
+pub fn hwmon_fan_control(hwmon: &Path) -> Option<FanControl> {
+    read_num(&hwmon.join("pwm1_enable")).map(FanControl::from_pwm_enable)
+}
+
+/// RPM against `fanN_max` when both exist, otherwise PWM duty (`pwmN` out of `pwmN_max`, normally 255).
+fn hwmon_fan_pct(hwmon: &Path, n: u32, rpm: Option<u32>) -> Option<f32> {
+    if let (Some(rpm), Some(max)) = (rpm, read_num::<u32>(&hwmon.join(format!("fan{n}_max"))))
+        && max > 0
+    {
+        return Some((rpm as f32 / max as f32 * 100.0).clamp(0.0, 100.0));
+    }
+    let pwm = read_num::<f32>(&hwmon.join(format!("pwm{n}")))?;
+    let pwm_max = read_num::<f32>(&hwmon.join(format!("pwm{n}_max"))).unwrap_or(255.0);
+    (pwm_max > 0.0).then(|| (pwm / pwm_max * 100.0).clamp(0.0, 100.0))
+}
+
+pub fn bytes_to_mb(bytes: u64) -> u32 {
+    (bytes / (1024 * 1024)) as u32
+}
+
+pub fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+/// Linux only; elsewhere (or if the process already exited) this is `None`.
+pub fn process_name(pid: u32) -> Option<String> {
+    read_trimmed(Path::new(&format!("/proc/{pid}/comm")))
+}
+
+/// Whether reading `path` fails with EACCES, e.g. root-only `power1_average`.
+/// Only worth asking once a read has already come back empty.
+pub fn read_denied(path: &Path) -> bool {
+    fs::read(path).is_err_and(|e| e.kind() == io::ErrorKind::PermissionDenied)
+}
+
+pub fn read_num<T: FromStr>(path: &Path) -> Option<T> {
+    read_trimmed(path)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn link_speed_maps_to_generation() {
+        assert_eq!(pcie_gen("16.0 GT/s PCIe"), Some(4));
+        assert_eq!(pcie_gen("2.5 GT/s"), Some(1));
+        assert_eq!(pcie_gen("Unknown"), None);
+    }
+
+    #[test]
+    fn fans_are_read_until_the_first_gap() {
+        let dir = std::env::temp_dir().join(format!("gtop-hwmon-fans-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for (file, value) in [("fan1_input", "1500"), ("fan1_max", "3000"), ("pwm2", "51"), ("fan4_input", "900")] {
+            fs::write(dir.join(file), value).unwrap();
+        }
+        let fans = hwmon_fans(&dir);
+        let no_control = hwmon_fan_control(&dir);
+        fs::write(dir.join("pwm1_enable"), "1\n").unwrap();
+        let control = hwmon_fan_control(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(fans.len(), 2);
+        assert_eq!((fans[0].index, fans[0].rpm, fans[0].pct), (0, Some(1500), Some(50.0)));
+        assert_eq!((fans[1].index, fans[1].rpm, fans[1].pct), (1, None, Some(20.0)));
+        assert_eq!((no_control, control), (None, Some(FanControl::Manual)));
+    }
+}
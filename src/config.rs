@@ -0,0 +1,253 @@
+//! `config.toml` loading (thresholds, alerts, UI settings) and saving changed preferences.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use clap::ValueEnum;
+use serde::Deserialize;
+use toml_edit::DocumentMut;
+
+use crate::app::{GaugeLabels, GaugeLayout, GaugeSet, GpuSeparator, RedrawMode};
+use crate::style::{CardHealth, Palette, TempUnit, ThemeName, Thresholds};
+
+/// `[alert]`: edge-triggered warning when a GPU's core temp goes critical.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct AlertConfig {
+    /// Core temp (°C) that trips the alert; defaults to the red temp threshold.
+    pub temp_c: Option<f32>,
+    /// How far back below `temp_c` it has to drop before the alert clears.
+    pub hysteresis_c: f32,
+    /// Ring the terminal bell when an alert fires.
+    pub bell: bool,
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self { temp_c: None, hysteresis_c: 5.0, bell: false }
+    }
+}
+
+impl AlertConfig {
+    /// A negative hysteresis would clear the alert while the card is still
+    /// over the line, so it would fire again on the very next sample.
+    pub fn check(&self) -> Result<(), String> {
+        if self.hysteresis_c < 0.0 {
+            return Err(format!("hysteresis_c ({}) can't be negative", self.hysteresis_c));
+        }
+        Ok(())
+    }
+}
+
+/// `[ui]`: interface behavior that isn't worth a command-line flag.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct UiConfig {
+    /// Ask before `q` / Ctrl-C quits (Esc always quits straight away).
+    pub confirm_quit: bool,
+    /// Seconds of history for the sparklines/chart; `--history` overrides it.
+    pub history: u64,
+    /// Decimal places for temperatures and power (0-2); `d` cycles it at runtime.
+    pub precision: usize,
+    /// e.g. `","` or `"."`: groups clock, VRAM and fan RPM digits in the UI
+    /// (never in `--no-tui` / export output). Off by default.
+    pub thousands_separator: Option<char>,
+    /// Samples in the utilization moving average that `m` switches to.
+    pub smoothing: usize,
+    /// Which readings color a struggling card's panel border.
+    pub card_health: CardHealth,
+    /// `"colorblind"` swaps the threshold hues and marks values `·` / `~` / `!`.
+    pub palette: Palette,
+    /// `"on-change"` skips repainting until a sample differs or a key is pressed.
+    pub redraw: RedrawMode,
+    // The starting `Preferences`. Flags override them, and changing them while
+    // running writes them back here on exit.
+    pub temp_unit: TempUnit,
+    pub theme: ThemeName,
+    pub layout: GaugeLayout,
+    /// `"title"` moves gauge readings into the frame title, `"off"` drops them; `g` cycles it.
+    pub gauge_labels: GaugeLabels,
+    /// `"blank"` or `"rule"` between compact rows; `b` cycles it.
+    pub separator: GpuSeparator,
+    /// e.g. `["util", "vram"]`; the ones left out start hidden.
+    pub gauges: GaugeSet,
+    pub interval_ms: u64,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            confirm_quit: false,
+            history: 120,
+            precision: 1,
+            thousands_separator: None,
+            smoothing: 5,
+            card_health: CardHealth::default(),
+            palette: Palette::default(),
+            redraw: RedrawMode::default(),
+            temp_unit: TempUnit::default(),
+            theme: ThemeName::default(),
+            layout: GaugeLayout::default(),
+            gauge_labels: GaugeLabels::default(),
+            separator: GpuSeparator::default(),
+            gauges: GaugeSet::default(),
+            interval_ms: 500,
+        }
+    }
+}
+
+/// Settings the user can change at runtime that are worth keeping between launches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Preferences {
+    pub temp_unit: TempUnit,
+    pub theme: ThemeName,
+    pub layout: GaugeLayout,
+    pub gauge_labels: GaugeLabels,
+    pub separator: GpuSeparator,
+    pub gauges: GaugeSet,
+    pub interval_ms: u64,
+}
+
+impl Preferences {
+    /// `[ui]` keys whose value in `self` differs from `before`.
+    fn changed_since(&self, before: &Self) -> Vec<(&'static str, toml_edit::Value)> {
+        let mut changed = vec![];
+        if self.temp_unit != before.temp_unit {
+            changed.push(("temp_unit", enum_value(self.temp_unit)));
+        }
+        if self.theme != before.theme {
+            changed.push(("theme", enum_value(self.theme)));
+        }
+        if self.layout != before.layout {
+            changed.push(("layout", enum_value(self.layout)));
+        }
+        if self.gauge_labels != before.gauge_labels {
+            changed.push(("gauge_labels", enum_value(self.gauge_labels)));
+        }
+        if self.separator != before.separator {
+            changed.push(("separator", enum_value(self.separator)));
+        }
+        if self.gauges != before.gauges {
+            changed.push(("gauges", self.gauges.iter().map(enum_value).collect::<toml_edit::Array>().into()));
+        }
+        if self.interval_ms != before.interval_ms {
+            changed.push(("interval_ms", toml_edit::Value::from(self.interval_ms as i64)));
+        }
+        changed
+    }
+}
+
+/// Same spelling as on the command line (and what `Deserialize` accepts).
+fn enum_value(v: impl ValueEnum) -> toml_edit::Value {
+    v.to_possible_value().map(|p| p.get_name().to_string()).unwrap_or_default().into()
+}
+
+/// `~/.config/gtop/config.toml` (or `--config`). Every key is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub thresholds: Thresholds,
+    pub alert: AlertConfig,
+    pub ui: UiConfig,
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/gtop/config.toml`, falling back to `~/.config/gtop/config.toml`.
+    fn default_path() -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+        Some(base.join("gtop").join("config.toml"))
+    }
+
+    /// An explicit path has to exist; the default one is allowed to be missing.
+    pub fn load(explicit: Option<&Path>) -> anyhow::Result<Self> {
+        let path = match explicit {
+            Some(p) => p.to_path_buf(),
+            None => match Self::default_path() {
+                Some(p) if p.exists() => p,
+                _ => return Ok(Self::default()),
+            },
+        };
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read config {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("invalid config {}", path.display()))
+    }
+
+    /// Writes whatever changed between `before` and `after` into `[ui]`,
+    /// keeping the rest of the file (comments included) as it was. Creates
+    /// the file and its directory if needed; does nothing if nothing changed.
+    pub fn save_preferences(explicit: Option<&Path>, before: &Preferences, after: &Preferences) -> anyhow::Result<()> {
+        let changed = after.changed_since(before);
+        if changed.is_empty() {
+            return Ok(());
+        }
+        let path = explicit
+            .map(Path::to_path_buf)
+            .or_else(Self::default_path)
+            .context("nowhere to save settings: neither $XDG_CONFIG_HOME nor $HOME is set")?;
+        let mut doc = match fs::read_to_string(&path) {
+            Ok(text) => text
+                .parse::<DocumentMut>()
+                .with_context(|| format!("invalid config {}", path.display()))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => DocumentMut::new(),
+            Err(e) => return Err(e).with_context(|| format!("failed to read config {}", path.display())),
+        };
+        let ui = doc
+            .entry("ui")
+            .or_insert(toml_edit::table())
+            .as_table_like_mut()
+            .with_context(|| format!("`ui` in {} is not a table", path.display()))?;
+        for (key, value) in changed {
+            ui.insert(key, toml_edit::Item::Value(value));
+        }
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        fs::write(&path, doc.to_string()).with_context(|| format!("failed to save settings to {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negative_hysteresis_is_rejected() {
+        assert_eq!(AlertConfig::default().check(), Ok(()));
+        let config: Config = toml::from_str("[alert]\nhysteresis_c = -2\n").unwrap();
+        assert_eq!(config.alert.check(), Err("hysteresis_c (-2) can't be negative".into()));
+    }
+
+    #[test]
+    fn saving_preferences_only_touches_changed_keys() {
+        let dir = std::env::temp_dir().join(format!("gtop-config-test-{}", std::process::id()));
+        let path = dir.join("nested").join("config.toml");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "# my settings\n[ui]\nhistory = 300 # keep\n").unwrap();
+
+        let before = Preferences {
+            temp_unit: TempUnit::Celsius,
+            theme: ThemeName::Default,
+            layout: GaugeLayout::Vertical,
+            gauge_labels: GaugeLabels::Bar,
+            separator: GpuSeparator::None,
+            gauges: GaugeSet::default(),
+            interval_ms: 500,
+        };
+        let after = Preferences { temp_unit: TempUnit::Fahrenheit, ..before };
+        Config::save_preferences(Some(&path), &before, &after).unwrap();
+
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.contains("# my settings") && text.contains("history = 300 # keep"), "{text}");
+        assert!(text.contains("temp_unit = \"fahrenheit\""), "{text}");
+        assert!(!text.contains("theme"), "{text}");
+        let config = Config::load(Some(&path)).unwrap();
+        assert_eq!(config.ui.temp_unit, TempUnit::Fahrenheit);
+        assert_eq!(config.ui.history, 300);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
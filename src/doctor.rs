@@ -0,0 +1,85 @@
+//! `--doctor`: which backends come up on this machine and what each one can
+//! actually read, as plain text to paste into a bug report.
+
+use std::io::{self, Write};
+use std::thread;
+use std::time::Duration;
+
+use clap::ValueEnum;
+
+use crate::backend::{BackendKind, describe_drm, open_backend};
+use crate::metrics::GpuMetrics;
+
+/// Each backend is sampled twice, `interval` apart, and reported from the
+/// second: Intel utilization, engine busy and some power readings are
+/// counter deltas that a first sample can't have yet.
+pub fn run(out: &mut dyn Write, interval: Duration) -> io::Result<()> {
+    for &kind in BackendKind::value_variants() {
+        if kind == BackendKind::Mock {
+            continue;
+        }
+        let label = kind.to_possible_value().map(|v| v.get_name().to_string()).unwrap_or_default();
+        let mut backend = match open_backend(Some(kind)) {
+            Ok(backend) => backend,
+            Err(e) => {
+                writeln!(out, "{label}: unavailable: {e:#}")?;
+                continue;
+            }
+        };
+        writeln!(out, "{label}: initialized ({})", backend.name())?;
+        let sampled = backend.sample().and_then(|_| {
+            thread::sleep(interval);
+            backend.sample()
+        });
+        match sampled {
+            Ok(gpus) if gpus.is_empty() => writeln!(out, "  no GPUs reported")?,
+            Ok(gpus) => {
+                for (i, gpu) in gpus.iter().enumerate() {
+                    describe_gpu(out, i, gpu)?;
+                }
+            }
+            Err(e) => writeln!(out, "  sample failed: {e}")?,
+        }
+    }
+    writeln!(out)?;
+    describe_drm(out)
+}
+
+fn describe_gpu(out: &mut dyn Write, idx: usize, gpu: &GpuMetrics) -> io::Result<()> {
+    let readings = [
+        ("temperature", gpu.temperature_c.is_some()),
+        ("junction temp", gpu.junction_temp_c.is_some()),
+        ("mem temp", gpu.mem_temp_c.is_some()),
+        ("utilization", gpu.utilization_pct.is_some()),
+        ("memory busy", gpu.mem_utilization_pct.is_some()),
+        ("engine utilization", !gpu.engine_utilization.is_empty()),
+        ("VRAM used", gpu.vram_used_mb.is_some()),
+        ("VRAM total", gpu.vram_total_mb.is_some()),
+        ("power", gpu.power_w.is_some()),
+        ("chip power", gpu.gpu_chip_power_w.is_some()),
+        ("power limit", gpu.power_limit_w.is_some()),
+        ("fans", !gpu.fans.is_empty()),
+        ("fan control", gpu.fan_control.is_some()),
+        ("ECC errors", gpu.ecc_errors.is_some()),
+        ("core clock", gpu.core_clock_mhz.is_some()),
+        ("mem clock", gpu.mem_clock_mhz.is_some()),
+        ("core clock max", gpu.core_clock_max_mhz.is_some()),
+        ("mem clock max", gpu.mem_clock_max_mhz.is_some()),
+        ("perf state", gpu.perf_state.is_some()),
+        ("PCIe link", gpu.pcie_gen.is_some() || gpu.pcie_width.is_some()),
+        ("driver version", gpu.driver_version.is_some()),
+        ("VBIOS version", gpu.vbios_version.is_some()),
+        ("processes", !gpu.processes.is_empty()),
+    ];
+    let list = |available: bool| {
+        let names: Vec<&str> = readings.iter().filter(|r| r.1 == available).map(|r| r.0).collect();
+        if names.is_empty() { "none".to_string() } else { names.join(", ") }
+    };
+    writeln!(out, "  GPU {idx}: {}", gpu.name)?;
+    writeln!(out, "    available: {}", list(true))?;
+    writeln!(out, "    missing:   {}", list(false))?;
+    if !gpu.unreadable.is_empty() {
+        writeln!(out, "    permission denied: {}", gpu.unreadable.join(", "))?;
+    }
+    Ok(())
+}
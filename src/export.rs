@@ -0,0 +1,129 @@
+//! Writing samples out: the `--log` CSV, `s` JSON snapshots and `--json-stream`.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::metrics::GpuMetrics;
+
+const CSV_HEADER: &str = "timestamp,gpu,name,temperature_c,junction_temp_c,mem_temp_c,\
+utilization_pct,vram_used_mb,vram_total_mb,power_w,fan_rpm,fan_pct,core_clock_mhz,mem_clock_mhz";
+
+/// Flushed at most this often so a crash/kill loses at most ~1s of rows.
+const CSV_FLUSH_EVERY: Duration = Duration::from_secs(1);
+
+/// `--log` writer. Appends to an existing file; the header only goes into a new/empty one.
+pub struct CsvLogger {
+    out: BufWriter<File>,
+    last_flush: Instant,
+}
+
+impl CsvLogger {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let empty = file.metadata()?.len() == 0;
+        let mut out = BufWriter::new(file);
+        if empty {
+            writeln!(out, "{CSV_HEADER}")?;
+        }
+        Ok(Self { out, last_flush: Instant::now() })
+    }
+
+    pub fn log(&mut self, metrics: &[GpuMetrics]) -> io::Result<()> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        for (i, gpu) in metrics.iter().enumerate().filter(|(_, gpu)| !gpu.disconnected) {
+            writeln!(self.out, "{}", csv_row(now, i, gpu))?;
+        }
+        if self.last_flush.elapsed() >= CSV_FLUSH_EVERY {
+            self.out.flush()?;
+            self.last_flush = Instant::now();
+        }
+        Ok(())
+    }
+}
+
+/// `None` fields become empty cells. Timestamp is unix seconds with ms precision.
+fn csv_row(unix: Duration, idx: usize, gpu: &GpuMetrics) -> String {
+    fn cell<T: std::fmt::Display>(v: &Option<T>) -> String {
+        v.as_ref().map(|x| x.to_string()).unwrap_or_default()
+    }
+    // Names can have commas in them; quote and double any embedded quotes
+    let name = format!("\"{}\"", gpu.name.replace('"', "\"\""));
+    [
+        format!("{:.3}", unix.as_secs_f64()),
+        idx.to_string(),
+        name,
+        cell(&gpu.temperature_c),
+        cell(&gpu.junction_temp_c),
+        cell(&gpu.mem_temp_c),
+        cell(&gpu.utilization_pct),
+        cell(&gpu.vram_used_mb),
+        cell(&gpu.vram_total_mb),
+        cell(&gpu.power_w),
+        cell(&gpu.fan_rpm()),
+        cell(&gpu.fan_pct()),
+        cell(&gpu.core_clock_mhz),
+        cell(&gpu.mem_clock_mhz),
+    ]
+    .join(",")
+}
+
+#[derive(Serialize)]
+struct StreamLine<'a> {
+    timestamp_ms: u64,
+    gpus: Vec<StreamGpu<'a>>,
+}
+
+#[derive(Serialize)]
+struct StreamGpu<'a> {
+    index: usize,
+    #[serde(flatten)]
+    gpu: &'a GpuMetrics,
+}
+
+/// One `--json-stream` line, `{"timestamp_ms": ..., "gpus": [{"index": 0, ...}]}`,
+/// flushed straight away so whatever is reading the pipe sees it now.
+pub fn write_json_line<'a>(
+    out: &mut impl Write,
+    gpus: impl IntoIterator<Item = (usize, &'a GpuMetrics)>,
+) -> io::Result<()> {
+    let line = StreamLine {
+        timestamp_ms: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+        gpus: gpus.into_iter().map(|(index, gpu)| StreamGpu { index, gpu }).collect(),
+    };
+    serde_json::to_writer(&mut *out, &line)?;
+    writeln!(out)?;
+    out.flush()
+}
+
+/// Writes `metrics` as pretty JSON to `gtop-snapshot-<unix secs>.json` in the cwd.
+pub fn write_snapshot(metrics: &[GpuMetrics]) -> io::Result<PathBuf> {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let path = PathBuf::from(format!("gtop-snapshot-{secs}.json"));
+    let json = serde_json::to_string_pretty(metrics)?;
+    fs::write(&path, json)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_stream_line_has_index_and_epoch_timestamps() {
+        let gpu = GpuMetrics { name: "Test GPU".into(), ..Default::default() };
+        let mut out = vec![];
+        write_json_line(&mut out, [(2, &gpu)]).unwrap();
+
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text.matches('\n').count(), 1);
+        let line: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(line["gpus"][0]["index"], 2);
+        assert_eq!(line["gpus"][0]["name"], "Test GPU");
+        // Unix ms, not an `Instant`
+        assert!(line["gpus"][0]["timestamp_ms"].as_u64().unwrap() > 1_600_000_000_000);
+    }
+}
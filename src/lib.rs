@@ -0,0 +1,15 @@
+//! Everything but the command line, split out of the binary so the benches
+//! under `benches/` can reach the backends and the renderer.
+
+pub mod app;
+pub mod backend;
+pub mod config;
+pub mod doctor;
+pub mod export;
+pub mod metrics;
+pub mod sampler;
+pub mod screenshot;
+pub mod server;
+pub mod style;
+pub mod terminal;
+pub mod ui;
@@ -1,344 +1,475 @@
-use std::io;
+use std::io::{self, Write};
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::thread;
 use std::time::{Duration, Instant};
 
-use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
-use ratatui::{
-    backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout},
-    text::{Line, Span, Text},
-    widgets::{Block, Borders, Paragraph, Gauge},
-    style::{Color, Style},
-    Terminal,
-};
-
-#[derive(Debug, Clone)]
-struct GpuMetrics {
-    name: String,
-    temperature_c: Option<f32>,
-    junction_temp_c: Option<f32>,
-    mem_temp_c: Option<f32>,
-
-    utilization_pct: Option<f32>,
-    vram_used_mb: Option<u32>,
-    vram_total_mb: Option<u32>,
-
-    power_w: Option<f32>,
-    fan_rpm: Option<u32>,
+use anyhow::Context;
+use clap::Parser;
+use crossterm::event::{self, Event, KeyEventKind, MouseEventKind};
 
-    core_clock_mhz: Option<u32>,
-    mem_clock_mhz: Option<u32>,
-
-    timestamp: Instant,
-}
-
-fn fmt_opt<T: std::fmt::Display>(v: &Option<T>) -> String {
-    v.as_ref().map(|x| x.to_string()).unwrap_or_else(|| "--".into())
+use gtop::app::{
+    App, GaugeLabels, GaugeLayout, GpuFilter, GpuSeparator, MAX_PRECISION, MIN_INTERVAL_MS, NO_GPUS, RedrawMode, SortKey,
+};
+use gtop::backend::remote::RemoteBackend;
+use gtop::backend::replay::{Recorder, ReplayBackend};
+use gtop::backend::{BackendKind, GpuBackend, open_backends};
+use gtop::config::{Config, UiConfig};
+use gtop::doctor;
+use gtop::export::{CsvLogger, write_json_line};
+use gtop::metrics::{VramUnit, fmt_opt, fmt_vram};
+use gtop::screenshot::write_screenshot;
+use gtop::server::{SharedMetrics, spawn_daemon, spawn_metrics_server, spawn_socket_daemon};
+use gtop::style::{Palette, Theme, ThemeName, Thresholds};
+use gtop::terminal::{TerminalSession, Tui};
+use gtop::ui::{render_error, ui};
+
+#[derive(Parser, Debug)]
+#[command(name = "gtop", about = "Terminal GPU monitor", version = env!("GTOP_VERSION"))]
+struct Cli {
+    /// Metrics source; several (e.g. `nvidia,amd`) are shown together. Without it we use
+    /// every one of NVML, AMD sysfs (or else rocm-smi), Intel and Apple (macOS) that finds a
+    /// GPU, falling back to the mock.
+    #[arg(long, value_enum, value_delimiter = ',')]
+    backend: Vec<BackendKind>,
+
+    /// Refresh interval in milliseconds (min 50) [default: 500, or `[ui] interval_ms` from config]
+    #[arg(long, value_name = "MS", value_parser = clap::value_parser!(u64).range(MIN_INTERVAL_MS..))]
+    interval: Option<u64>,
+
+    /// Seconds of history kept for the sparklines and temperature chart [default: 120, or `[ui] history` from config]
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    history: Option<u64>,
+
+    /// Append one CSV row per GPU per sample to this file
+    #[arg(long, value_name = "FILE")]
+    log: Option<PathBuf>,
+
+    /// Serve Prometheus metrics on http://ADDR/metrics (e.g. 0.0.0.0:9835)
+    #[arg(long, value_name = "ADDR")]
+    serve: Option<String>,
+
+    /// Serve samples to `gtop --connect` clients on this TCP port, without a UI
+    #[arg(long, value_name = "PORT")]
+    daemon: Option<u16>,
+
+    /// Address --daemon listens on. Loopback unless told otherwise, since the
+    /// protocol has no authentication: reach it through `ssh -L`, or pass
+    /// 0.0.0.0 on a network you trust
+    #[arg(long, value_name = "ADDR", default_value = "127.0.0.1", requires = "daemon")]
+    bind: IpAddr,
+
+    /// Like --daemon, but on a Unix domain socket at PATH, for clients on this machine
+    #[arg(long, value_name = "PATH")]
+    socket: Option<PathBuf>,
+
+    /// Show the GPUs of a `gtop --daemon` running at HOST:PORT, or of a
+    /// `gtop --socket` when given a path (anything with a `/` in it)
+    #[arg(long, value_name = "HOST:PORT", conflicts_with_all = ["backend", "replay"])]
+    connect: Option<String>,
+
+    /// No terminal UI: print a plain table once and exit (with --serve, keep sampling silently instead)
+    #[arg(long)]
+    no_tui: bool,
+
+    /// With --no-tui, reprint the table every interval instead of exiting
+    #[arg(long, requires = "no_tui")]
+    watch: bool,
+
+    /// No terminal UI: write one JSON object per sample to stdout (JSON lines) until killed
+    #[arg(long, conflicts_with = "watch")]
+    json_stream: bool,
+
+    /// Config file (default: ~/.config/gtop/config.toml)
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// VRAM display units
+    #[arg(long, value_enum, default_value_t = VramUnit::Auto)]
+    units: VramUnit,
+
+    /// Which GPUs to show; `busy` hides the ones under --busy-threshold utilization
+    #[arg(long, value_enum, default_value_t = GpuFilter::All)]
+    filter: GpuFilter,
+
+    /// Only ever show these GPUs, by backend index (e.g. 0,2,3)
+    #[arg(long, value_name = "LIST", value_delimiter = ',')]
+    gpus: Vec<usize>,
+
+    /// Utilization (%) a GPU needs to stay visible with `--filter busy`
+    #[arg(long, value_name = "PCT", default_value_t = 5.0)]
+    busy_threshold: f32,
+
+    /// Only draw the N highest GPUs by --top-by, re-ranked every sample; the
+    /// rest share a summary line (change with n / N)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    top: Option<u64>,
+
+    /// What --top ranks by
+    #[arg(long, value_enum, value_name = "METRIC", default_value_t = SortKey::Temperature, requires = "top")]
+    top_by: SortKey,
+
+    /// Gauge arrangement inside each GPU panel [default: vertical, or `[ui] layout` from config]
+    #[arg(long, value_enum)]
+    layout: Option<GaugeLayout>,
+
+    /// Where gauge readings go: on the bar, in the frame title, or off (cycle with `g`) [default: bar, or `[ui] gauge_labels` from config]
+    #[arg(long, value_enum, value_name = "WHERE")]
+    gauge_labels: Option<GaugeLabels>,
+
+    /// What goes between GPU rows in compact mode (cycle with `b`) [default: none, or `[ui] separator` from config]
+    #[arg(long, value_enum)]
+    separator: Option<GpuSeparator>,
+
+    /// Just one line of colored util/temp/VRAM bars per GPU (toggle with `c`)
+    #[arg(long)]
+    compact: bool,
+
+    /// Color theme [default: default, or `[ui] theme` from config]
+    #[arg(long, value_enum)]
+    theme: Option<ThemeName>,
+
+    /// Threshold colors: `colorblind` is blue/orange/white with a symbol
+    /// before each value [default: standard, or `[ui] palette` from config]
+    #[arg(long, value_enum)]
+    palette: Option<Palette>,
+
+    /// Repaint every tick, or only when a sample changes or a key is pressed
+    /// (easier on a battery) [default: always, or `[ui] redraw` from config]
+    #[arg(long, value_enum, value_name = "WHEN")]
+    redraw: Option<RedrawMode>,
+
+    /// Core temperature (°C) that turns yellow, overriding the config
+    #[arg(long, value_name = "C")]
+    temp_warn: Option<f32>,
+
+    /// Core temperature (°C) that turns red, overriding the config
+    #[arg(long, value_name = "C")]
+    temp_crit: Option<f32>,
+
+    /// VRAM fill (0-1) that turns yellow, overriding the config
+    #[arg(long, value_name = "RATIO")]
+    vram_warn: Option<f32>,
+
+    /// VRAM fill (0-1) that turns red, overriding the config
+    #[arg(long, value_name = "RATIO")]
+    vram_crit: Option<f32>,
+
+    /// Power draw (W) that turns yellow, overriding the config
+    #[arg(long, value_name = "W")]
+    power_warn: Option<f32>,
+
+    /// Power draw (W) that turns red, overriding the config
+    #[arg(long, value_name = "W")]
+    power_crit: Option<f32>,
+
+    /// Ring the terminal bell when a GPU crosses the critical temperature
+    #[arg(long)]
+    bell: bool,
+
+    /// Save every sample to FILE (JSON lines) for later --replay
+    #[arg(long, value_name = "FILE")]
+    record: Option<PathBuf>,
+
+    /// Click a GPU to select it and scroll the process list with the wheel
+    /// (off by default: capturing the mouse breaks the terminal's own text selection)
+    #[arg(long)]
+    mouse: bool,
+
+    /// Stop sampling and redrawing while the terminal window isn't focused
+    /// (needs a terminal that reports focus changes; others are unaffected)
+    #[arg(long)]
+    pause_on_blur: bool,
+
+    /// Probe every backend, print what each one can read, and exit
+    #[arg(long)]
+    doctor: bool,
+
+    /// Play back a --record file instead of reading real hardware
+    #[arg(long, value_name = "FILE", conflicts_with = "backend")]
+    replay: Option<PathBuf>,
 }
 
-fn fmt_vram(used: Option<u32>, total: Option<u32>) -> String {
-    match (used, total) {
-        (Some(u), Some(t)) => format!("{u} / {t} MB"),
-        (Some(u), None) => format!("{u} MB / ?"),
-        _ => "--".into(),
+impl Cli {
+    /// `--temp-warn` and friends on top of whatever the config set.
+    fn thresholds(&self, mut t: Thresholds) -> Thresholds {
+        let overrides = [
+            (&mut t.temp.warn, self.temp_warn),
+            (&mut t.temp.crit, self.temp_crit),
+            (&mut t.vram.warn, self.vram_warn),
+            (&mut t.vram.crit, self.vram_crit),
+            (&mut t.power.warn, self.power_warn),
+            (&mut t.power.crit, self.power_crit),
+        ];
+        for (limit, flag) in overrides {
+            if let Some(v) = flag {
+                *limit = v;
+            }
+        }
+        t
     }
 }
 
-fn vram_ratio(used: Option<u32>, total: Option<u32>) -> f64 {
-    match (used, total) {
-        (Some(u), Some(t)) if t > 0 => (u as f64 / t as f64).clamp(0.0, 1.0),
-        _ => 0.0,
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(&cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            // `{:#}` gives the one-line context chain, e.g.
+            // "failed to open AMD sysfs backend: Permission denied (os error 13)".
+            eprintln!("gtop: {e:#}");
+            ExitCode::FAILURE
+        }
     }
 }
 
-fn pct_ratio(pct: Option<f32>) -> f64 {
-    pct.map(|p| (p.clamp(0.0, 100.0) as f64) / 100.0).unwrap_or(0.0)
-}
-
-fn gauge_style(r: f64) -> Style {
-    if r >= 0.90 {
-        Style::default().fg(Color::Red)
-    } else if r >= 0.75 {
-        Style::default().fg(Color::Yellow)
-    } else {
-        Style::default().fg(Color::Green)
+/// By the time this returns (`Ok` or `Err`) the terminal has been restored.
+fn run(cli: &Cli) -> anyhow::Result<()> {
+    if cli.doctor {
+        println!("gtop {}", env!("GTOP_VERSION"));
+        let interval = Duration::from_millis(cli.interval.unwrap_or(UiConfig::default().interval_ms));
+        return Ok(doctor::run(&mut io::stdout().lock(), interval)?);
     }
-}
-
-fn temp_style(temp_c: Option<f32>) -> Style {
-    match temp_c {
-        Some(t) if t >= 90.0 => Style::default().fg(Color::Red),
-        Some(t) if t >= 80.0 => Style::default().fg(Color::Yellow),
-        Some(_) => Style::default().fg(Color::Green),
-        None => Style::default().fg(Color::DarkGray),
+    let app = setup(cli);
+    if cli.no_tui || cli.json_stream || cli.daemon.is_some() || cli.socket.is_some() {
+        return Ok(run_headless(&mut app?, cli.watch, cli.json_stream)?);
     }
-}
 
-fn power_style(power_w: Option<f32>) -> Style {
-    match power_w {
-        Some(p) if p >= 300.0 => Style::default().fg(Color::Red),
-        Some(p) if p >= 220.0 => Style::default().fg(Color::Yellow),
-        Some(_) => Style::default().fg(Color::Green),
-        None => Style::default().fg(Color::DarkGray),
+    let mut session = TerminalSession::enter(cli.mouse, cli.pause_on_blur)?;
+    match app {
+        Ok(mut app) => {
+            let before = app.preferences();
+            run_app(&mut session.terminal, &mut app)?;
+            drop(session);
+            Config::save_preferences(cli.config.as_deref(), &before, &app.preferences())
+        }
+        Err(e) => {
+            show_error(&mut session.terminal, &format!("{e:#}"))?;
+            Err(e)
+        }
     }
 }
 
-fn junction_style(temp_c: Option<f32>) -> Style {
-    match temp_c {
-        Some(t) if t >= 105.0 => Style::default().fg(Color::Red),
-        Some(t) if t >= 95.0 => Style::default().fg(Color::Yellow),
-        Some(_) => Style::default().fg(Color::Green),
-        None => Style::default().fg(Color::DarkGray),
-    }
-}
+/// Everything that can fail before the first frame: config, backend, outputs.
+fn setup(cli: &Cli) -> anyhow::Result<App> {
+    let config = Config::load(cli.config.as_deref())?;
+    let gpu_backend: Box<dyn GpuBackend> = match (&cli.replay, &cli.connect) {
+        (Some(path), _) => Box::new(
+            ReplayBackend::open(path)
+                .with_context(|| format!("failed to load recording {}", path.display()))?,
+        ),
+        (None, Some(path)) if path.contains('/') => Box::new(RemoteBackend::unix(path)),
+        (None, Some(addr)) => Box::new(RemoteBackend::new(addr)),
+        (None, None) => open_backends(&cli.backend)?,
+    };
 
-fn mem_temp_style(temp_c: Option<f32>) -> Style {
-    match temp_c {
-        Some(t) if t >= 95.0 => Style::default().fg(Color::Red),
-        Some(t) if t >= 85.0 => Style::default().fg(Color::Yellow),
-        Some(_) => Style::default().fg(Color::Green),
-        None => Style::default().fg(Color::DarkGray),
+    let mut app = App::new(
+        gpu_backend,
+        Duration::from_millis(cli.interval.unwrap_or(config.ui.interval_ms).max(MIN_INTERVAL_MS)),
+        Duration::from_secs(cli.history.unwrap_or(config.ui.history).max(1)),
+    )
+    .context("failed to start the sampling thread")?;
+    app.thresholds = cli.thresholds(config.thresholds);
+    app.thresholds.check().map_err(anyhow::Error::msg).context("invalid thresholds")?;
+    config.alert.check().map_err(anyhow::Error::msg).context("invalid [alert]")?;
+    app.theme_name = cli.theme.unwrap_or(config.ui.theme);
+    app.theme = Theme::new(app.theme_name).with_palette(cli.palette.unwrap_or(config.ui.palette));
+    app.temp_unit = config.ui.temp_unit;
+    app.vram_unit = cli.units;
+    app.filter = cli.filter;
+    if !cli.gpus.is_empty() {
+        let mut ids = cli.gpus.clone();
+        ids.sort_unstable();
+        ids.dedup();
+        app.gpus = Some(ids);
     }
+    app.layout = cli.layout.unwrap_or(config.ui.layout);
+    app.redraw = cli.redraw.unwrap_or(config.ui.redraw);
+    app.gauge_labels = cli.gauge_labels.unwrap_or(config.ui.gauge_labels);
+    app.separator = cli.separator.unwrap_or(config.ui.separator);
+    app.gauges = config.ui.gauges;
+    app.compact = cli.compact;
+    app.pause_on_blur = cli.pause_on_blur;
+    app.busy_threshold = cli.busy_threshold;
+    if let Some(n) = cli.top {
+        app.top_n = Some(n as usize);
+        app.sort = cli.top_by;
+    }
+    app.alert = config.alert;
+    app.alert.bell |= cli.bell;
+    app.confirm_quit = config.ui.confirm_quit;
+    app.precision = config.ui.precision.min(MAX_PRECISION);
+    app.thousands_sep = config.ui.thousands_separator;
+    app.card_health = config.ui.card_health;
+    app.smoothing = config.ui.smoothing.max(1);
+    if let Some(path) = &cli.log {
+        let log = CsvLogger::open(path)
+            .with_context(|| format!("failed to open log file {}", path.display()))?;
+        app.csv_log = Some(log);
+    }
+    if let Some(path) = &cli.record {
+        let rec = Recorder::create(path)
+            .with_context(|| format!("failed to create recording {}", path.display()))?;
+        app.recorder = Some(rec);
+    }
+    if let Some(addr) = &cli.serve {
+        let shared = app.shared.get_or_insert_with(SharedMetrics::default).clone();
+        spawn_metrics_server(addr, shared).with_context(|| format!("failed to listen on {addr}"))?;
+    }
+    if let Some(port) = cli.daemon {
+        let shared = app.shared.get_or_insert_with(SharedMetrics::default).clone();
+        let addr = SocketAddr::new(cli.bind, port);
+        spawn_daemon(&addr.to_string(), shared).with_context(|| format!("failed to listen on {addr}"))?;
+    }
+    if let Some(path) = &cli.socket {
+        let shared = app.shared.get_or_insert_with(SharedMetrics::default).clone();
+        let file = spawn_socket_daemon(path, shared)
+            .with_context(|| format!("failed to listen on {}", path.display()))?;
+        app.socket_file = Some(file);
+    }
+    Ok(app)
 }
 
-
-
-
-/// Fake sampler for macOS/dev. Later I gotta replace this with:
-/// - AMD sysfs reader, OR
-/// - rocm-smi JSON parser, OR
-/// - Intel backend, etc.
-fn sample_fake(counter: u64) -> Vec<GpuMetrics> {
-    // Give it a little “motion” so you can see updates.
-    let temp = 45.0 + ((counter % 30) as f32) * 0.3;      // ~45–54C
-    let util = (counter % 100) as f32;                    // 0–99%
-    let used = 1200 + (counter as u32 % 800);             // 1200–1999 MB
-    let total = 16_384;
-    let junction = temp + 12.0 + ((counter % 10) as f32) * 0.2; // hotspot higher
-    let mem_temp = temp + 6.0;                                  // vram a bit higher
-    let core_clk = 800 + (counter as u32 % 1600);               // 800–2399 MHz
-    let mem_clk  = 1000 + (counter as u32 % 800);  
-
-    vec![GpuMetrics {
-        name: "AMD Radeon (mock)".to_string(),
-        temperature_c: Some(temp),
-        utilization_pct: Some(util),
-        vram_used_mb: Some(used),
-        vram_total_mb: Some(total),
-        power_w: Some(90.0 + (counter % 20) as f32),
-        fan_rpm: Some(1200 + (counter as u32 % 400)),
-        junction_temp_c: Some(junction),
-        mem_temp_c: Some(mem_temp),
-        core_clock_mhz: Some(core_clk),
-        mem_clock_mhz: Some(mem_clk),
-        timestamp: Instant::now(),
-    }]
-}
-
-struct App {
-    running: bool,
-    tick: u64,
-    metrics: Vec<GpuMetrics>,
-}
-
-impl App {
-    fn new() -> Self {
-        Self {
-            running: true,
-            tick: 0,
-            metrics: vec![],
+/// Shows a startup failure inside the TUI until any key is pressed; the same
+/// message is printed to stderr once the terminal is restored.
+fn show_error(terminal: &mut Tui, message: &str) -> io::Result<()> {
+    loop {
+        terminal.draw(|f| render_error(f, message))?;
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            return Ok(());
         }
     }
+}
 
-    fn on_tick(&mut self) {
-        self.metrics = sample_fake(self.tick);
-        self.tick += 1;
-    }
-
-    fn on_key(&mut self, code: KeyCode) {
-        match code {
-            KeyCode::Char('q') | KeyCode::Esc => self.running = false,
-            _ => {}
+/// `--no-tui`: never touches raw mode or the alternate screen, so it's fine in
+/// pipes/CI. One table and exit by default; `--watch` reprints every interval and
+/// `--serve` keeps sampling (silently, unless also watching) until killed.
+/// `--json-stream` also runs until killed, or until its reader goes away.
+fn run_headless(app: &mut App, watch: bool, json_stream: bool) -> io::Result<()> {
+    let once = !watch && !json_stream && app.shared.is_none();
+    let print = watch || once;
+    let mut last_streamed = None;
+    loop {
+        app.on_tick();
+        if let Some(flash) = app.take_flash()
+            && flash.error
+        {
+            eprintln!("gtop: {}", flash.text);
         }
+        if let Some(status) = app.status.take() {
+            if once {
+                return Err(io::Error::other(status));
+            }
+            eprintln!("gtop: {status}");
+        }
+        // Only an empty first answer; cards that go away later just drop out
+        if app.samples == 1 && app.no_gpus() {
+            return Err(io::Error::other(NO_GPUS));
+        }
+        if print {
+            print_table(&mut io::stdout().lock(), app)?;
+        }
+        // Only fresh samples: a slow backend leaves the same readings up for a few ticks
+        let newest = app.metrics.iter().map(|gpu| gpu.timestamp).max();
+        if json_stream && newest.is_some() && newest != last_streamed {
+            last_streamed = newest;
+            let gpus = app
+                .metrics
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| !app.is_disconnected(i))
+                .map(|(i, gpu)| (app.device_index(i), gpu));
+            match write_json_line(&mut io::stdout().lock(), gpus) {
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+                result => result?,
+            }
+        }
+        if once {
+            return Ok(());
+        }
+        thread::sleep(app.tick_rate);
     }
 }
 
-fn main() -> io::Result<()> {
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
-
-    let res = run_app(&mut terminal);
-
-    disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-    terminal.show_cursor()?;
-
-    res
+fn print_table(out: &mut impl Write, app: &App) -> io::Result<()> {
+    let temp = |t: Option<f32>| t.map(|t| format!("{t:.1}")).unwrap_or("--".into());
+    writeln!(
+        out,
+        "{:<4} {:<28} {:>6} {:>6} {:>6} {:>5} {:>17} {:>6} {:>6} {:>6} {:>6}",
+        "GPU", "NAME", "TEMP", "JUNC", "MEM", "UTIL", "VRAM", "POWER", "FAN", "CORE", "MEMCLK"
+    )?;
+    for (i, gpu) in app.metrics.iter().enumerate() {
+        writeln!(
+            out,
+            "{:<4} {:<28} {:>6} {:>6} {:>6} {:>5} {:>17} {:>6} {:>6} {:>6} {:>6}",
+            app.device_index(i),
+            gpu.name,
+            temp(gpu.temperature_c),
+            temp(gpu.junction_temp_c),
+            temp(gpu.mem_temp_c),
+            gpu.utilization_pct.map(|u| format!("{u:.0}%")).unwrap_or("--".into()),
+            fmt_vram(gpu.vram_used_mb, gpu.vram_total_mb, app.vram_unit),
+            gpu.power_w.map(|p| format!("{p:.0}W")).unwrap_or("--".into()),
+            fmt_opt(&gpu.fan_rpm()),
+            fmt_opt(&gpu.core_clock_mhz),
+            fmt_opt(&gpu.mem_clock_mhz),
+        )?;
+    }
+    writeln!(out)
 }
 
-fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> io::Result<()> {
-    let mut app = App::new();
-    let tick_rate = Duration::from_millis(500);
-
+fn run_app(terminal: &mut Tui, app: &mut App) -> io::Result<()> {
     // Force first tick so UI isn’t empty
     app.on_tick();
+    let mut last_tick = Instant::now();
 
     while app.running {
-        terminal.draw(|f| ui(f, &app))?;
-
-        // Input (non-blocking with timeout)
-        if event::poll(tick_rate)? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    app.on_key(key.code);
-                }
+        // Nothing to draw for or sample until focus comes back
+        if app.blurred {
+            if let Some(event) = next_event(app.tick_rate)? {
+                app.on_event(event);
             }
-        } else {
-            // Timeout hit => "tick"
+            continue;
+        }
+        if std::mem::take(&mut app.dirty) || app.redraw == RedrawMode::Always {
+            let frame = terminal.draw(|f| ui(f, app))?;
+            if std::mem::take(&mut app.screenshot_pending) {
+                app.screenshot_taken(write_screenshot(frame.buffer));
+            }
+        }
+
+        // Input until the next tick is due; a stream of events (mouse
+        // motion, held keys) mustn't keep pushing the tick back
+        if let Some(event) = next_event(app.tick_rate.saturating_sub(last_tick.elapsed()))? {
+            app.on_event(event);
+        }
+        if last_tick.elapsed() >= app.tick_rate {
             app.on_tick();
+            last_tick = Instant::now();
+        }
+
+        if std::mem::take(&mut app.bell_pending) {
+            let out = terminal.backend_mut();
+            out.write_all(b"\x07")?;
+            out.flush()?;
         }
     }
 
     Ok(())
 }
 
-fn ui(f: &mut ratatui::Frame, app: &App) {
-    let size = f.size();
-
-    let layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Length(3), Constraint::Min(0), Constraint::Length(3)])
-        .split(size);
-
-    let header = Paragraph::new("gtop — mock metrics mode (MacBook) — q to quit")
-        .block(Block::default().borders(Borders::ALL).title("Header"));
-    f.render_widget(header, layout[0]);
-
-    let main = Block::default().borders(Borders::ALL).title("GPU Metrics");
-    f.render_widget(main.clone(), layout[1]);
-
-    // Inner area inside the main block
-    let inner = main.inner(layout[1]);
-
-    // Split the main inner area into:
-// - a text area
-// - a small gauge area at the bottom
-let inner_chunks = Layout::default()
-    .direction(Direction::Vertical)
-    .constraints([Constraint::Min(0), Constraint::Length(3), Constraint::Length(3)])
-    .split(inner);
-
-
-// Text lines (same as before, but remove the VRAM line)
-let mut lines: Vec<Line> = vec![];
-
-for (i, gpu) in app.metrics.iter().enumerate() {
-    if i > 0 {
-        lines.push(Line::from("")); // blank line between GPUs
+/// The next input event within `timeout`, if any. Pointer motion, which
+/// `--mouse` reports on every move, has nothing to act on and is dropped here.
+fn next_event(timeout: Duration) -> io::Result<Option<Event>> {
+    if !event::poll(timeout)? {
+        return Ok(None);
     }
-
-    lines.push(Line::from(format!("GPU {i}: {}", gpu.name)));
-   
-    //lines.push(Line::from(format!(
-        //"Util: {} %",
-        //gpu.utilization_pct.map(|u| format!("{u:.0}")).unwrap_or("--".into())
-    //)));
-
-
-    // Temp line (colored)
-let temp_str = gpu.temperature_c.map(|t| format!("{t:.1}")).unwrap_or("--".into());
-lines.push(Line::from(vec![
-    Span::raw("Temp: "),
-    Span::styled(format!("{temp_str} °C"), temp_style(gpu.temperature_c)),
-]));
-
-// Junction line (colored)
-let junction_str = gpu.junction_temp_c.map(|t| format!("{t:.1}")).unwrap_or("--".into());
-lines.push(Line::from(vec![
-    Span::raw("Junction: "),
-    Span::styled(format!("{junction_str} °C"), junction_style(gpu.junction_temp_c)),
-]));
-
-// Mem Temp line (colored)
-let mem_str = gpu.mem_temp_c.map(|t| format!("{t:.1}")).unwrap_or("--".into());
-lines.push(Line::from(vec![
-    Span::raw("Mem Temp: "),
-    Span::styled(format!("{mem_str} °C"), mem_temp_style(gpu.mem_temp_c)),
-]));
-
-
-// Power line (colored)
-let power_str = gpu.power_w.map(|p| format!("{p:.0}")).unwrap_or("--".into());
-lines.push(Line::from(vec![
-    Span::raw("Power: "),
-    Span::styled(format!("{power_str} W"), power_style(gpu.power_w)),
-]));
-
-lines.push(Line::from(format!(
-    "Clocks: core {} MHz | mem {} MHz",
-    gpu.core_clock_mhz.map(|c| c.to_string()).unwrap_or("--".into()),
-    gpu.mem_clock_mhz.map(|c| c.to_string()).unwrap_or("--".into()),
-)));
-
-    lines.push(Line::from(format!("Fan: {} RPM", fmt_opt(&gpu.fan_rpm))));
+    Ok(match event::read()? {
+        Event::Mouse(mouse) if mouse.kind == MouseEventKind::Moved => None,
+        event => Some(event),
+    })
 }
 
-let body = Paragraph::new(Text::from(lines));
-f.render_widget(body, inner_chunks[0]);
-
-// VRAM gauge (for now: based on GPU 0)
-let gpu0 = app.metrics.get(0);
-let (ratio, label) = if let Some(gpu) = gpu0 {
-    let r = vram_ratio(gpu.vram_used_mb, gpu.vram_total_mb);
-    let lbl = match (gpu.vram_used_mb, gpu.vram_total_mb) {
-        (Some(u), Some(t)) => format!("VRAM {u} / {t} MB"),
-        (Some(u), None) => format!("VRAM {u} / ? MB"),
-        _ => "VRAM --".into(),
-    };
-    (r, lbl)
-} else {
-    (0.0, "VRAM --".into())
-};
-
-let vram_gauge = Gauge::default()
-    .block(Block::default().borders(Borders::ALL).title("VRAM Usage"))
-    .gauge_style(gauge_style(ratio))
-    .ratio(ratio)
-    .label(label);
-
-
-
-// Utilization gauge (for now: based on GPU 0)
-let gpu0 = app.metrics.get(0);
-let (util_ratio, util_label) = if let Some(gpu) = gpu0 {
-    let r = pct_ratio(gpu.utilization_pct);
-    let lbl = gpu
-        .utilization_pct
-        .map(|u| format!("GPU Util {:.0}%", u))
-        .unwrap_or_else(|| "GPU Util --".into());
-    (r, lbl)
-} else {
-    (0.0, "GPU Util --".into())
-};
-
-let util_gauge = Gauge::default()
-    .block(Block::default().borders(Borders::ALL).title("Utilization"))
-    .gauge_style(gauge_style(util_ratio))
-    .ratio(util_ratio)
-    .label(util_label);
-
-f.render_widget(util_gauge, inner_chunks[1]);
-
-
-f.render_widget(vram_gauge, inner_chunks[2]);
-
-    let footer = Paragraph::new(format!("Tick: {}   (data is mocked)", app.tick))
-        .block(Block::default().borders(Borders::ALL).title("Footer"));
-    f.render_widget(footer, layout[2]);
-}
\ No newline at end of file
@@ -0,0 +1,444 @@
+//! The backend-neutral sample types and the formatting helpers shared by every output.
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// Missing fields fall back to `Default`, so older recordings still load.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GpuMetrics {
+    pub name: String,
+    /// Whose card it is, set by the backend that read it. Names don't always
+    /// say ("AMD GPU (card1)" does, "Tesla T4" doesn't).
+    pub vendor: Vendor,
+    pub temperature_c: Option<f32>,
+    pub junction_temp_c: Option<f32>,
+    pub mem_temp_c: Option<f32>,
+
+    pub utilization_pct: Option<f32>,
+    /// How busy the memory controller is, not how full VRAM is: a card can
+    /// be nearly empty and still saturating its bandwidth.
+    pub mem_utilization_pct: Option<f32>,
+    /// Per engine (graphics, compute, copy, video...), in the driver's own
+    /// names, where it reports them. `utilization_pct` stays the headline.
+    pub engine_utilization: Vec<(String, f32)>,
+    pub vram_used_mb: Option<u32>,
+    pub vram_total_mb: Option<u32>,
+
+    /// Whole-board draw: what the power gauge, limits and energy use.
+    pub power_w: Option<f32>,
+    /// The GPU die alone, where the driver has a separate sensor for it
+    /// (NVML's GPU power scope). Other tools quote one or the other, so
+    /// the two are shown side by side rather than swapped.
+    pub gpu_chip_power_w: Option<f32>,
+    /// The card's board power limit (what it throttles at), for drawing power as a gauge.
+    pub power_limit_w: Option<f32>,
+    /// Every fan the backend can see, in its own order; empty if none.
+    pub fans: Vec<FanMetrics>,
+    /// Whether the driver or the user is setting the fan speed. Only read
+    /// where the driver exposes it (amdgpu's `pwm1_enable`).
+    pub fan_control: Option<FanControl>,
+
+    pub core_clock_mhz: Option<u32>,
+    pub mem_clock_mhz: Option<u32>,
+    /// Top of the card's clock range, for drawing the clocks as gauges.
+    pub core_clock_max_mhz: Option<u32>,
+    pub mem_clock_max_mhz: Option<u32>,
+    /// Where the card sits between idle and full boost, in the driver's own
+    /// terms: NVIDIA's "P0" (fastest) to "P12", or amdgpu's forced level
+    /// and current sclk level.
+    pub perf_state: Option<String>,
+
+    /// Uncorrectable VRAM ECC errors since the driver loaded. `None` when
+    /// the card has no ECC (or it's off); any non-zero count is bad news.
+    pub ecc_errors: Option<u64>,
+
+    /// Negotiated PCIe link: generation (1-6) and lane count.
+    pub pcie_gen: Option<u8>,
+    pub pcie_width: Option<u8>,
+
+    /// Read once when the backend starts; these don't change while it runs.
+    pub driver_version: Option<String>,
+    pub vbios_version: Option<String>,
+
+    /// Why clocks are being held back right now ("thermal", "power", ...).
+    /// Empty when the card isn't throttling or the backend can't tell.
+    pub throttle_reasons: Vec<String>,
+
+    /// Readings that exist but we weren't allowed to read ("power",
+    /// "temperature"), so the UI can say why they're `--`.
+    pub unreadable: Vec<String>,
+
+    /// Empty when there are none *or* the backend can't enumerate them.
+    pub processes: Vec<GpuProcess>,
+
+    /// Not read this time: the last readings of a card whose backend failed,
+    /// resent by `CompositeBackend` so the cards after it keep their positions.
+    pub disconnected: bool,
+
+    #[serde(
+        rename = "timestamp_ms",
+        serialize_with = "serialize_epoch_ms",
+        deserialize_with = "deserialize_epoch_ms"
+    )]
+    pub timestamp: Instant,
+}
+
+/// Same readings, whenever they were taken: `timestamp` is left out so an
+/// unchanged card compares equal from one sample to the next.
+impl PartialEq for GpuMetrics {
+    fn eq(&self, other: &Self) -> bool {
+        // Destructured so a new field can't be forgotten here
+        let GpuMetrics {
+            name,
+            vendor,
+            temperature_c,
+            junction_temp_c,
+            mem_temp_c,
+            utilization_pct,
+            mem_utilization_pct,
+            engine_utilization,
+            vram_used_mb,
+            vram_total_mb,
+            power_w,
+            gpu_chip_power_w,
+            power_limit_w,
+            fans,
+            fan_control,
+            core_clock_mhz,
+            mem_clock_mhz,
+            core_clock_max_mhz,
+            mem_clock_max_mhz,
+            perf_state,
+            ecc_errors,
+            pcie_gen,
+            pcie_width,
+            driver_version,
+            vbios_version,
+            throttle_reasons,
+            unreadable,
+            processes,
+            disconnected,
+            timestamp: _,
+        } = self;
+        *name == other.name
+            && *vendor == other.vendor
+            && *temperature_c == other.temperature_c
+            && *junction_temp_c == other.junction_temp_c
+            && *mem_temp_c == other.mem_temp_c
+            && *utilization_pct == other.utilization_pct
+            && *mem_utilization_pct == other.mem_utilization_pct
+            && *engine_utilization == other.engine_utilization
+            && *vram_used_mb == other.vram_used_mb
+            && *vram_total_mb == other.vram_total_mb
+            && *power_w == other.power_w
+            && *gpu_chip_power_w == other.gpu_chip_power_w
+            && *power_limit_w == other.power_limit_w
+            && *fans == other.fans
+            && *fan_control == other.fan_control
+            && *core_clock_mhz == other.core_clock_mhz
+            && *mem_clock_mhz == other.mem_clock_mhz
+            && *core_clock_max_mhz == other.core_clock_max_mhz
+            && *mem_clock_max_mhz == other.mem_clock_max_mhz
+            && *perf_state == other.perf_state
+            && *ecc_errors == other.ecc_errors
+            && *pcie_gen == other.pcie_gen
+            && *pcie_width == other.pcie_width
+            && *driver_version == other.driver_version
+            && *vbios_version == other.vbios_version
+            && *throttle_reasons == other.throttle_reasons
+            && *unreadable == other.unreadable
+            && *processes == other.processes
+            && *disconnected == other.disconnected
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Vendor {
+    /// Recordings and remotes from before the field existed.
+    #[default]
+    Unknown,
+    Nvidia,
+    Amd,
+    Intel,
+    Apple,
+}
+
+impl Vendor {
+    pub fn label(self) -> &'static str {
+        match self {
+            Vendor::Unknown => "unknown",
+            Vendor::Nvidia => "NVIDIA",
+            Vendor::Amd => "AMD",
+            Vendor::Intel => "Intel",
+            Vendor::Apple => "Apple",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FanMetrics {
+    /// 0-based (hwmon's `fan1` is 0).
+    pub index: u32,
+    pub rpm: Option<u32>,
+    /// How hard the fan is working, 0-100 (RPM relative to its max, or PWM duty).
+    pub pct: Option<f32>,
+}
+
+/// hwmon's `pwmN_enable`: 0 is no control (full speed), 1 manual, 2 and up
+/// some flavor of automatic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FanControl {
+    Auto,
+    Manual,
+    Full,
+}
+
+impl FanControl {
+    pub fn from_pwm_enable(value: u32) -> Self {
+        match value {
+            0 => FanControl::Full,
+            1 => FanControl::Manual,
+            _ => FanControl::Auto,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FanControl::Auto => "auto",
+            FanControl::Manual => "manual",
+            FanControl::Full => "full speed",
+        }
+    }
+}
+
+impl GpuMetrics {
+    /// First fan's RPM, for the places that only have room for one number.
+    pub fn fan_rpm(&self) -> Option<u32> {
+        self.fans.first()?.rpm
+    }
+
+    /// First fan's duty, like `fan_rpm`.
+    pub fn fan_pct(&self) -> Option<f32> {
+        self.fans.first()?.pct
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GpuProcess {
+    pub pid: u32,
+    pub name: String,
+    pub used_mb: Option<u32>,
+    /// Busiest engine's share over the last interval, where the driver
+    /// reports per-client engine time (DRM fdinfo).
+    pub util_pct: Option<f32>,
+}
+
+impl Default for GpuMetrics {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            vendor: Vendor::Unknown,
+            temperature_c: None,
+            junction_temp_c: None,
+            mem_temp_c: None,
+            utilization_pct: None,
+            mem_utilization_pct: None,
+            engine_utilization: vec![],
+            vram_used_mb: None,
+            vram_total_mb: None,
+            power_w: None,
+            gpu_chip_power_w: None,
+            power_limit_w: None,
+            fans: vec![],
+            fan_control: None,
+            core_clock_mhz: None,
+            mem_clock_mhz: None,
+            core_clock_max_mhz: None,
+            mem_clock_max_mhz: None,
+            perf_state: None,
+            ecc_errors: None,
+            pcie_gen: None,
+            pcie_width: None,
+            driver_version: None,
+            vbios_version: None,
+            throttle_reasons: vec![],
+            unreadable: vec![],
+            processes: vec![],
+            disconnected: false,
+            timestamp: Instant::now(),
+        }
+    }
+}
+
+/// `Instant` has no absolute meaning, so anchor it against the wall clock now.
+fn instant_to_epoch_ms(t: Instant) -> u64 {
+    let wall = SystemTime::now() - t.elapsed();
+    wall.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+/// Inverse of `instant_to_epoch_ms`; times in the future or before boot clamp to now.
+fn epoch_ms_to_instant(ms: u64) -> Instant {
+    let now_ms = instant_to_epoch_ms(Instant::now());
+    Instant::now()
+        .checked_sub(Duration::from_millis(now_ms.saturating_sub(ms)))
+        .unwrap_or_else(Instant::now)
+}
+
+fn serialize_epoch_ms<S: serde::Serializer>(t: &Instant, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_u64(instant_to_epoch_ms(*t))
+}
+
+fn deserialize_epoch_ms<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Instant, D::Error> {
+    Ok(epoch_ms_to_instant(u64::deserialize(d)?))
+}
+
+pub fn fmt_opt<T: std::fmt::Display>(v: &Option<T>) -> String {
+    v.as_ref().map(|x| x.to_string()).unwrap_or_else(|| "--".into())
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VramUnit {
+    Mb,
+    Gib,
+    /// GiB once the card (or allocation, if total is unknown) is past 1 GiB
+    #[default]
+    Auto,
+}
+
+/// Backends report MiB (labelled MB throughout, like the vendors' tools do).
+pub fn fmt_vram(used: Option<u32>, total: Option<u32>, unit: VramUnit) -> String {
+    let gib = match unit {
+        VramUnit::Mb => false,
+        VramUnit::Gib => true,
+        VramUnit::Auto => total.or(used).is_some_and(|mb| mb > 1024),
+    };
+    let fmt = |mb: u32| {
+        if gib {
+            format!("{:.1}", mb as f64 / 1024.0)
+        } else {
+            mb.to_string()
+        }
+    };
+    let suffix = if gib { "GiB" } else { "MB" };
+    match (used, total) {
+        (Some(u), Some(t)) => format!("{} / {} {suffix}", fmt(u), fmt(t)),
+        (Some(u), None) => format!("{} {suffix} / ?", fmt(u)),
+        _ => "--".into(),
+    }
+}
+
+/// Puts `sep` between every three digits of each whole number in `text`
+/// ("16384 MB" -> "16,384 MB"). Digits after a decimal point are left alone.
+pub fn group_digits(text: &str, sep: char) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len() + 4);
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        while i < chars.len() && chars[i].is_ascii_digit() {
+            i += 1;
+        }
+        if start == i {
+            out.push(chars[i]);
+            i += 1;
+            continue;
+        }
+        let fraction = start > 0 && chars[start - 1] == '.';
+        for (k, &c) in chars[start..i].iter().enumerate() {
+            if !fraction && k > 0 && (i - start - k) % 3 == 0 {
+                out.push(sep);
+            }
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// `HH:MM:SS`, with hours growing past two digits rather than wrapping.
+pub fn fmt_hms(d: Duration) -> String {
+    let s = d.as_secs();
+    format!("{:02}:{:02}:{:02}", s / 3600, s / 60 % 60, s % 60)
+}
+
+/// Time of day (UTC) as `HH:MM:SS`; there's no timezone database to ask for local time.
+pub fn fmt_utc_time(t: SystemTime) -> String {
+    let secs = t.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    fmt_hms(Duration::from_secs(secs % 86_400))
+}
+
+pub fn vram_ratio(used: Option<u32>, total: Option<u32>) -> f64 {
+    match (used, total) {
+        (Some(u), Some(t)) if t > 0 => (u as f64 / t as f64).clamp(0.0, 1.0),
+        _ => 0.0,
+    }
+}
+
+pub fn pct_ratio(pct: Option<f32>) -> f64 {
+    pct.map(|p| (p.clamp(0.0, 100.0) as f64) / 100.0).unwrap_or(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vram_ratio_clamps_and_handles_missing_total() {
+        assert_eq!(vram_ratio(Some(512), Some(1024)), 0.5);
+        // Some drivers briefly report used > total while allocations settle
+        assert_eq!(vram_ratio(Some(2048), Some(1024)), 1.0);
+        assert_eq!(vram_ratio(Some(512), Some(0)), 0.0);
+        assert_eq!(vram_ratio(Some(512), None), 0.0);
+        assert_eq!(vram_ratio(None, None), 0.0);
+    }
+
+    #[test]
+    fn digit_grouping_skips_fractions() {
+        assert_eq!(group_digits("1229 / 16384 MB", ','), "1,229 / 16,384 MB");
+        assert_eq!(group_digits("2.0 GiB / 24.0 GiB", '.'), "2.0 GiB / 24.0 GiB");
+        assert_eq!(group_digits("1234567.5", '.'), "1.234.567.5");
+        assert_eq!(group_digits("Fan 1 38% (999 RPM)", ','), "Fan 1 38% (999 RPM)");
+    }
+
+    #[test]
+    fn hms_keeps_counting_past_a_day() {
+        assert_eq!(fmt_hms(Duration::from_millis(59_999)), "00:00:59");
+        assert_eq!(fmt_hms(Duration::from_secs(3 * 3600 + 7 * 60 + 5)), "03:07:05");
+        assert_eq!(fmt_hms(Duration::from_secs(100 * 3600)), "100:00:00");
+    }
+
+    #[test]
+    fn pct_ratio_clamps_to_unit_range() {
+        assert_eq!(pct_ratio(Some(50.0)), 0.5);
+        assert_eq!(pct_ratio(Some(250.0)), 1.0);
+        assert_eq!(pct_ratio(Some(-5.0)), 0.0);
+        assert_eq!(pct_ratio(None), 0.0);
+    }
+
+    #[test]
+    fn fmt_vram_units() {
+        assert_eq!(fmt_vram(Some(1229), Some(16384), VramUnit::Mb), "1229 / 16384 MB");
+        assert_eq!(fmt_vram(Some(12000), Some(24576), VramUnit::Auto), "11.7 / 24.0 GiB");
+        assert_eq!(fmt_vram(Some(300), Some(512), VramUnit::Auto), "300 / 512 MB");
+        assert_eq!(fmt_vram(Some(512), Some(1024), VramUnit::Gib), "0.5 / 1.0 GiB");
+    }
+
+    #[test]
+    fn fmt_vram_used_without_total() {
+        assert_eq!(fmt_vram(Some(800), None, VramUnit::Auto), "800 MB / ?");
+        assert_eq!(fmt_vram(Some(2048), None, VramUnit::Auto), "2.0 GiB / ?");
+        assert_eq!(fmt_vram(None, Some(1024), VramUnit::Auto), "--");
+        assert_eq!(fmt_vram(None, None, VramUnit::Mb), "--");
+    }
+
+    #[test]
+    fn fmt_opt_placeholder() {
+        assert_eq!(fmt_opt::<u32>(&None), "--");
+        assert_eq!(fmt_opt(&Some(1800)), "1800");
+    }
+}
@@ -0,0 +1,87 @@
+//! Runs the backend on its own thread so a hung driver call can't freeze the UI.
+
+use std::any::Any;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+
+use crate::backend::{BackendHealth, GpuBackend};
+use crate::metrics::GpuMetrics;
+
+/// Owns the sampling thread. Samples are taken on request, one at a time, so
+/// a slow backend never has reads piling up behind it.
+pub struct Sampler {
+    requests: Sender<()>,
+    results: Receiver<(io::Result<Vec<GpuMetrics>>, Vec<BackendHealth>)>,
+    /// The backend's `health()` as of the latest sample.
+    health: Vec<BackendHealth>,
+    /// A request is out and its answer hasn't come back yet.
+    pending: bool,
+    name: String,
+    mock: bool,
+}
+
+impl Sampler {
+    pub fn spawn(mut backend: Box<dyn GpuBackend>) -> io::Result<Self> {
+        let (name, mock) = (backend.name().to_string(), backend.is_mock());
+        let (requests, rx) = mpsc::channel::<()>();
+        let (tx, results) = mpsc::channel();
+        // Ends once `Sampler` (and with it the request channel) is dropped
+        thread::Builder::new().name("gtop-sampler".into()).spawn(move || {
+            for () in rx {
+                // A backend bug becomes a failed sample in the status line,
+                // not a dead thread and a UI stuck on its last readings.
+                let result = panic::catch_unwind(AssertUnwindSafe(|| backend.sample())).unwrap_or_else(|payload| {
+                    Err(io::Error::other(format!("backend panicked: {}", panic_message(&*payload))))
+                });
+                if tx.send((result, backend.health())).is_err() {
+                    break;
+                }
+            }
+        })?;
+        Ok(Self { requests, results, health: vec![], pending: false, name, mock })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_mock(&self) -> bool {
+        self.mock
+    }
+
+    pub fn health(&self) -> &[BackendHealth] {
+        &self.health
+    }
+
+    /// Asks for a sample (unless one is already on its way) and waits up to
+    /// `wait` for it. `None` means the backend is still busy; the answer is
+    /// picked up by a later call.
+    pub fn poll(&mut self, wait: Duration) -> Option<io::Result<Vec<GpuMetrics>>> {
+        if !self.pending {
+            if self.requests.send(()).is_err() {
+                return Some(Err(io::Error::other("sampling thread exited")));
+            }
+            self.pending = true;
+        }
+        match self.results.recv_timeout(wait) {
+            Ok((result, health)) => {
+                self.pending = false;
+                self.health = health;
+                Some(result)
+            }
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => Some(Err(io::Error::other("sampling thread exited"))),
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown cause")
+}
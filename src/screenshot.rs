@@ -0,0 +1,233 @@
+//! `x`: the last drawn frame as an ANSI dump (for `cat` / `less -R`) and an SVG
+//! (for bug reports and docs), both straight from ratatui's cell buffer.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ratatui::buffer::{Buffer, Cell};
+use ratatui::style::{Color, Modifier};
+
+/// Cell size in SVG user units, roughly a 14px monospace font.
+const CELL_W: usize = 8;
+const CELL_H: usize = 17;
+/// What `Color::Reset` stands for in the SVG, where there's no terminal to defer to.
+const DEFAULT_FG: &str = "#d0d0d0";
+const DEFAULT_BG: &str = "#1c1c1c";
+
+/// Writes `gtop-screen-<unix secs>.ans` and `.svg` to the cwd and returns the
+/// two paths' shared stem.
+pub fn write_screenshot(buffer: &Buffer) -> std::io::Result<PathBuf> {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let stem = PathBuf::from(format!("gtop-screen-{secs}"));
+    fs::write(stem.with_extension("ans"), to_ansi(buffer))?;
+    fs::write(stem.with_extension("svg"), to_svg(buffer))?;
+    Ok(stem)
+}
+
+fn rows(buffer: &Buffer) -> impl Iterator<Item = Vec<&Cell>> {
+    let area = buffer.area;
+    (area.top()..area.bottom()).map(move |y| (area.left()..area.right()).map(|x| buffer.get(x, y)).collect())
+}
+
+/// SGR parameters for one cell's colors and modifiers, starting from a reset.
+fn sgr(cell: &Cell) -> String {
+    let mut codes = vec!["0".to_string()];
+    let modifiers = [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::SLOW_BLINK, "5"),
+        (Modifier::REVERSED, "7"),
+        (Modifier::CROSSED_OUT, "9"),
+    ];
+    codes.extend(modifiers.iter().filter(|(m, _)| cell.modifier.contains(*m)).map(|(_, c)| c.to_string()));
+    codes.extend(ansi_color(cell.fg, false));
+    codes.extend(ansi_color(cell.bg, true));
+    codes.join(";")
+}
+
+fn ansi_color(color: Color, bg: bool) -> Option<String> {
+    let base = match color {
+        Color::Reset => return None,
+        Color::Indexed(n) => return Some(format!("{};5;{n}", if bg { 48 } else { 38 })),
+        Color::Rgb(r, g, b) => return Some(format!("{};2;{r};{g};{b}", if bg { 48 } else { 38 })),
+        Color::Black => 30,
+        Color::Red => 31,
+        Color::Green => 32,
+        Color::Yellow => 33,
+        Color::Blue => 34,
+        Color::Magenta => 35,
+        Color::Cyan => 36,
+        Color::Gray => 37,
+        Color::DarkGray => 90,
+        Color::LightRed => 91,
+        Color::LightGreen => 92,
+        Color::LightYellow => 93,
+        Color::LightBlue => 94,
+        Color::LightMagenta => 95,
+        Color::LightCyan => 96,
+        Color::White => 97,
+    };
+    Some((base + if bg { 10 } else { 0 }).to_string())
+}
+
+/// Escape codes only where the style changes, and a reset at every line end so
+/// a truncated `head` of the file doesn't leave the terminal colored.
+fn to_ansi(buffer: &Buffer) -> String {
+    let mut out = String::new();
+    for row in rows(buffer) {
+        let mut current = None;
+        for cell in row {
+            let style = sgr(cell);
+            if current.as_ref() != Some(&style) {
+                let _ = write!(out, "\x1b[{style}m");
+                current = Some(style);
+            }
+            out.push_str(cell.symbol());
+        }
+        out.push_str("\x1b[0m\n");
+    }
+    out
+}
+
+/// The xterm palette, so the SVG looks like the default terminal colors.
+fn rgb(color: Color) -> Option<(u8, u8, u8)> {
+    const ANSI: [(u8, u8, u8); 16] = [
+        (0, 0, 0),
+        (205, 0, 0),
+        (0, 205, 0),
+        (205, 205, 0),
+        (0, 0, 238),
+        (205, 0, 205),
+        (0, 205, 205),
+        (229, 229, 229),
+        (127, 127, 127),
+        (255, 0, 0),
+        (0, 255, 0),
+        (255, 255, 0),
+        (92, 92, 255),
+        (255, 0, 255),
+        (0, 255, 255),
+        (255, 255, 255),
+    ];
+    let indexed = |n: u8| match n {
+        0..=15 => ANSI[n as usize],
+        16..=231 => {
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            let n = n - 16;
+            (level(n / 36), level(n / 6 % 6), level(n % 6))
+        }
+        _ => {
+            let v = 8 + (n - 232) * 10;
+            (v, v, v)
+        }
+    };
+    Some(match color {
+        Color::Reset => return None,
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::Indexed(n) => indexed(n),
+        Color::Black => ANSI[0],
+        Color::Red => ANSI[1],
+        Color::Green => ANSI[2],
+        Color::Yellow => ANSI[3],
+        Color::Blue => ANSI[4],
+        Color::Magenta => ANSI[5],
+        Color::Cyan => ANSI[6],
+        Color::Gray => ANSI[7],
+        Color::DarkGray => ANSI[8],
+        Color::LightRed => ANSI[9],
+        Color::LightGreen => ANSI[10],
+        Color::LightYellow => ANSI[11],
+        Color::LightBlue => ANSI[12],
+        Color::LightMagenta => ANSI[13],
+        Color::LightCyan => ANSI[14],
+        Color::White => ANSI[15],
+    })
+}
+
+fn hex(color: Color, default: &str) -> String {
+    rgb(color).map(|(r, g, b)| format!("#{r:02x}{g:02x}{b:02x}")).unwrap_or_else(|| default.to_string())
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// One `<rect>` per cell with a background and one `<text>` per run of equally
+/// styled cells, each pinned to its column so font metrics can't drift it.
+fn to_svg(buffer: &Buffer) -> String {
+    let (w, h) = (buffer.area.width as usize * CELL_W, buffer.area.height as usize * CELL_H);
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n\
+         <rect width=\"100%\" height=\"100%\" fill=\"{DEFAULT_BG}\"/>\n\
+         <g font-family=\"monospace\" font-size=\"14\" xml:space=\"preserve\">\n"
+    );
+    for (y, row) in rows(buffer).enumerate() {
+        let top = y * CELL_H;
+        let mut x = 0;
+        while x < row.len() {
+            let cell = row[x];
+            let reversed = cell.modifier.contains(Modifier::REVERSED);
+            let (fg, bg) = if reversed {
+                (hex(cell.bg, DEFAULT_BG), hex(cell.fg, DEFAULT_FG))
+            } else {
+                (hex(cell.fg, DEFAULT_FG), hex(cell.bg, DEFAULT_BG))
+            };
+            let same = |c: &Cell| c.fg == cell.fg && c.bg == cell.bg && c.modifier == cell.modifier;
+            let run = row[x..].iter().take_while(|c| same(c)).count();
+            if bg != DEFAULT_BG {
+                let _ = writeln!(
+                    out,
+                    "<rect x=\"{}\" y=\"{top}\" width=\"{}\" height=\"{CELL_H}\" fill=\"{bg}\"/>",
+                    x * CELL_W,
+                    run * CELL_W,
+                );
+            }
+            let text: String = row[x..x + run].iter().map(|c| c.symbol()).collect();
+            if !text.trim().is_empty() {
+                let mut attrs = format!("fill=\"{fg}\"");
+                if cell.modifier.contains(Modifier::BOLD) {
+                    attrs.push_str(" font-weight=\"bold\"");
+                }
+                if cell.modifier.contains(Modifier::DIM) {
+                    attrs.push_str(" opacity=\"0.6\"");
+                }
+                let _ = writeln!(
+                    out,
+                    "<text x=\"{}\" y=\"{}\" textLength=\"{}\" {attrs}>{}</text>",
+                    x * CELL_W,
+                    top + CELL_H - 4,
+                    run * CELL_W,
+                    xml_escape(&text),
+                );
+            }
+            x += run;
+        }
+    }
+    out.push_str("</g>\n</svg>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::layout::Rect;
+    use ratatui::style::Style;
+
+    #[test]
+    fn ansi_and_svg_keep_text_and_colors() {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 6, 1));
+        buffer.set_string(0, 0, "ok", Style::default().fg(Color::Red));
+        buffer.set_string(2, 0, "<hot>", Style::default().bg(Color::Indexed(21)));
+
+        let ansi = to_ansi(&buffer);
+        assert_eq!(ansi, "\x1b[0;31mok\x1b[0;48;5;21m<hot\x1b[0m\n");
+
+        let svg = to_svg(&buffer);
+        assert!(svg.contains("fill=\"#cd0000\">ok</text>"), "{svg}");
+        assert!(svg.contains("fill=\"#0000ff\"/>") && svg.contains("&lt;hot</text>"), "{svg}");
+    }
+}
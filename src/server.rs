@@ -0,0 +1,168 @@
+//! `--serve`: a minimal HTTP endpoint with the latest sample in Prometheus text
+//! format, and `--daemon` / `--socket`: the same sample for `gtop --connect`
+//! clients, over TCP or a Unix domain socket.
+
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::backend::remote::write_frame;
+use crate::metrics::GpuMetrics;
+
+/// Latest successful sample, shared with the `--serve` / `--daemon` threads.
+pub type SharedMetrics = Arc<Mutex<Vec<GpuMetrics>>>;
+
+/// Binds up front (so a bad address fails before the TUI starts), then answers
+/// `GET /metrics` on a background thread, one connection at a time.
+pub fn spawn_metrics_server(addr: &str, shared: SharedMetrics) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            // A client that hangs up mid-request is its own problem
+            let _ = handle_metrics_request(stream, &shared);
+        }
+    });
+    Ok(())
+}
+
+/// Like `spawn_metrics_server`, but speaking the `backend::remote` protocol,
+/// with a thread per client since they stay connected.
+pub fn spawn_daemon(addr: &str, shared: SharedMetrics) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let shared = shared.clone();
+            thread::spawn(move || {
+                // Hanging up is how a client says goodbye
+                let _ = stream.set_nodelay(true).and_then(|()| serve_daemon_client(stream, &shared));
+            });
+        }
+    });
+    Ok(())
+}
+
+/// The socket file `spawn_socket_daemon` created; removed again on drop.
+#[derive(Debug)]
+pub struct SocketFile(PathBuf);
+
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// `spawn_daemon` for same-host clients: no port to open, and the socket
+/// file's permissions decide who may read. A socket left behind by a gtop
+/// that was killed is replaced; one that still answers is an error.
+#[cfg(unix)]
+pub fn spawn_socket_daemon(path: &Path, shared: SharedMetrics) -> io::Result<SocketFile> {
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    let listener = match UnixListener::bind(path) {
+        Err(e) if e.kind() == io::ErrorKind::AddrInUse => {
+            let in_use = |why: &str| io::Error::new(e.kind(), format!("{} {why}", path.display()));
+            if !fs::symlink_metadata(path)?.file_type().is_socket() {
+                return Err(in_use("already exists and isn't a socket"));
+            }
+            if UnixStream::connect(path).is_ok() {
+                return Err(in_use("is in use by another running gtop"));
+            }
+            fs::remove_file(path)?;
+            UnixListener::bind(path)?
+        }
+        result => result?,
+    };
+    let file = SocketFile(path.to_path_buf());
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let shared = shared.clone();
+            thread::spawn(move || {
+                let _ = serve_daemon_client(stream, &shared);
+            });
+        }
+    });
+    Ok(file)
+}
+
+#[cfg(not(unix))]
+pub fn spawn_socket_daemon(path: &Path, _shared: SharedMetrics) -> io::Result<SocketFile> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, format!("{}: Unix sockets need a Unix system", path.display())))
+}
+
+fn serve_daemon_client(mut stream: impl Read + Write, shared: &SharedMetrics) -> io::Result<()> {
+    let mut request = [0; 1];
+    loop {
+        stream.read_exact(&mut request)?;
+        let metrics = shared.lock().map(|m| m.clone()).unwrap_or_default();
+        write_frame(&mut stream, &metrics)?;
+    }
+}
+
+fn handle_metrics_request(mut stream: TcpStream, shared: &SharedMetrics) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or("");
+    let (status, body) = if path == "/metrics" {
+        let metrics = shared.lock().map(|m| m.clone()).unwrap_or_default();
+        ("200 OK", prometheus_text(&metrics))
+    } else {
+        ("404 Not Found", "not found, try /metrics\n".to_string())
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+/// Prometheus text exposition format, one gauge family per metric, labelled by gpu index + name.
+fn prometheus_text(metrics: &[GpuMetrics]) -> String {
+    type Getter = fn(&GpuMetrics) -> Option<f64>;
+    let families: [(&str, &str, Getter); 14] = [
+        ("temperature_celsius", "GPU core/edge temperature", |g| g.temperature_c.map(f64::from)),
+        ("junction_temperature_celsius", "GPU hotspot temperature", |g| g.junction_temp_c.map(f64::from)),
+        ("memory_temperature_celsius", "VRAM temperature", |g| g.mem_temp_c.map(f64::from)),
+        ("utilization_ratio", "GPU busy fraction (0-1)", |g| g.utilization_pct.map(|u| f64::from(u) / 100.0)),
+        ("memory_utilization_ratio", "Memory controller busy fraction (0-1)", |g| {
+            g.mem_utilization_pct.map(|u| f64::from(u) / 100.0)
+        }),
+        ("vram_used_bytes", "VRAM in use", |g| g.vram_used_mb.map(|m| f64::from(m) * 1024.0 * 1024.0)),
+        ("vram_total_bytes", "Total VRAM", |g| g.vram_total_mb.map(|m| f64::from(m) * 1024.0 * 1024.0)),
+        ("power_watts", "Board power draw", |g| g.power_w.map(f64::from)),
+        ("chip_power_watts", "GPU chip power draw, where reported separately", |g| {
+            g.gpu_chip_power_w.map(f64::from)
+        }),
+        ("fan_rpm", "Fan speed", |g| g.fan_rpm().map(f64::from)),
+        ("fan_ratio", "Fan duty relative to max (0-1)", |g| g.fan_pct().map(|p| f64::from(p) / 100.0)),
+        ("ecc_uncorrected_errors", "Uncorrectable VRAM ECC errors since driver load", |g| g.ecc_errors.map(|n| n as f64)),
+        ("core_clock_hertz", "Core clock", |g| g.core_clock_mhz.map(|c| f64::from(c) * 1e6)),
+        ("memory_clock_hertz", "Memory clock", |g| g.mem_clock_mhz.map(|c| f64::from(c) * 1e6)),
+    ];
+
+    let mut out = String::new();
+    for (name, help, get) in families {
+        out.push_str(&format!("# HELP gtop_gpu_{name} {help}\n# TYPE gtop_gpu_{name} gauge\n"));
+        // Skipped, not renumbered: a failed backend's cards are old readings
+        for (i, gpu) in metrics.iter().enumerate().filter(|(_, gpu)| !gpu.disconnected) {
+            if let Some(v) = get(gpu) {
+                // Readings are f32 underneath; don't export 45.599998474121094
+                let v = (v * 1000.0).round() / 1000.0;
+                let label = prometheus_escape(&gpu.name);
+                out.push_str(&format!("gtop_gpu_{name}{{gpu=\"{i}\",name=\"{label}\"}} {v}\n"));
+            }
+        }
+    }
+    out
+}
+
+fn prometheus_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
@@ -0,0 +1,512 @@
+//! Thresholds, themes and the severity styling every metric goes through.
+
+use clap::ValueEnum;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Span;
+use serde::Deserialize;
+
+use crate::metrics::GpuMetrics;
+
+/// A yellow/red pair. Temps are Celsius, power is watts, gauges (VRAM included) are a 0..1 fill ratio.
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    pub warn: f32,
+    pub crit: f32,
+}
+
+impl Limits {
+    /// "<80", "80–90", "≥90" plus `unit`: what `severity` treats as normal,
+    /// warn and critical, with `scale` putting the limits in display units.
+    fn bands(self, scale: impl Fn(f32) -> f32, unit: &str) -> [String; 3] {
+        let (warn, crit) = (limit_text(scale(self.warn)), limit_text(scale(self.crit)));
+        [format!("<{warn}{unit}"), format!("{warn}–{crit}{unit}"), format!("≥{crit}{unit}")]
+    }
+}
+
+/// Whole numbers without the ".0", anything else to one decimal.
+fn limit_text(v: f32) -> String {
+    let v = (v * 10.0).round() / 10.0;
+    if v.fract() == 0.0 { format!("{v:.0}") } else { format!("{v:.1}") }
+}
+
+/// `l`: each styled reading's normal / warn / critical bands, temps in `unit`.
+/// Built from the same limits the `*_style` helpers read, so it can't drift.
+pub fn legend(t: &Thresholds, unit: TempUnit) -> Vec<(&'static str, [String; 3])> {
+    let temp_unit = format!(" {}", unit.suffix());
+    let temp = |l: Limits| l.bands(|c| unit.convert(c), &temp_unit);
+    let pct = |l: Limits| l.bands(|r| r * 100.0, "%");
+    vec![
+        ("Temp", temp(t.temp)),
+        ("Junction", temp(t.junction)),
+        ("Mem temp", temp(t.mem_temp)),
+        ("Power", t.power.bands(|w| w, " W")),
+        ("Gauges", pct(t.gauge)),
+        ("VRAM", pct(t.vram)),
+    ]
+}
+
+/// Color cutoffs for every styled metric, overridable from `[thresholds.*]` in the config.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(from = "ThresholdOverrides")]
+pub struct Thresholds {
+    pub temp: Limits,
+    pub junction: Limits,
+    pub mem_temp: Limits,
+    pub power: Limits,
+    pub gauge: Limits,
+    pub vram: Limits,
+}
+
+impl Thresholds {
+    /// A warn at or past crit would make the yellow band unreachable, which
+    /// is always a typo rather than a preference.
+    pub fn check(&self) -> Result<(), String> {
+        let all = [
+            ("temp", self.temp),
+            ("junction", self.junction),
+            ("mem_temp", self.mem_temp),
+            ("power", self.power),
+            ("gauge", self.gauge),
+            ("vram", self.vram),
+        ];
+        match all.iter().find(|(_, l)| l.warn >= l.crit) {
+            Some((name, l)) => Err(format!("{name} warn ({}) must be below its crit ({})", l.warn, l.crit)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            temp: Limits { warn: 80.0, crit: 90.0 },
+            junction: Limits { warn: 95.0, crit: 105.0 },
+            mem_temp: Limits { warn: 85.0, crit: 95.0 },
+            power: Limits { warn: 220.0, crit: 300.0 },
+            gauge: Limits { warn: 0.75, crit: 0.90 },
+            vram: Limits { warn: 0.75, crit: 0.90 },
+        }
+    }
+}
+
+/// `[thresholds.*]` as written: either side of any pair can be left out,
+/// keeping its default, so `[thresholds.temp] warn = 75` is enough.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ThresholdOverrides {
+    temp: LimitOverrides,
+    junction: LimitOverrides,
+    mem_temp: LimitOverrides,
+    power: LimitOverrides,
+    gauge: LimitOverrides,
+    vram: LimitOverrides,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct LimitOverrides {
+    warn: Option<f32>,
+    crit: Option<f32>,
+}
+
+impl LimitOverrides {
+    fn onto(self, l: Limits) -> Limits {
+        Limits { warn: self.warn.unwrap_or(l.warn), crit: self.crit.unwrap_or(l.crit) }
+    }
+}
+
+impl From<ThresholdOverrides> for Thresholds {
+    fn from(o: ThresholdOverrides) -> Self {
+        let d = Thresholds::default();
+        Self {
+            temp: o.temp.onto(d.temp),
+            junction: o.junction.onto(d.junction),
+            mem_temp: o.mem_temp.onto(d.mem_temp),
+            power: o.power.onto(d.power),
+            gauge: o.gauge.onto(d.gauge),
+            vram: o.vram.onto(d.vram),
+        }
+    }
+}
+
+/// How bad a reading is, independent of how that gets drawn. Ordered from
+/// least to most worrying, so the worst of several is their `max`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Unknown,
+    Normal,
+    Warn,
+    Critical,
+}
+
+/// `[ui.card_health]`: which readings count toward a card's overall health
+/// (its panel border). All of them unless switched off.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct CardHealth {
+    pub temp: bool,
+    pub junction: bool,
+    pub mem_temp: bool,
+    pub power: bool,
+}
+
+impl Default for CardHealth {
+    fn default() -> Self {
+        Self { temp: true, junction: true, mem_temp: true, power: true }
+    }
+}
+
+impl CardHealth {
+    /// The worst of the chosen readings against their own limits.
+    pub fn severity(&self, gpu: &GpuMetrics, t: &Thresholds) -> Severity {
+        [
+            (self.temp, gpu.temperature_c, t.temp),
+            (self.junction, gpu.junction_temp_c, t.junction),
+            (self.mem_temp, gpu.mem_temp_c, t.mem_temp),
+            (self.power, gpu.power_w, t.power),
+        ]
+        .into_iter()
+        .filter(|&(chosen, _, _)| chosen)
+        .map(|(_, v, l)| severity(v, l.warn, l.crit))
+        .max()
+        .unwrap_or(Severity::Unknown)
+    }
+}
+
+/// Border color for a card in trouble; `None` while it's fine (or unknown),
+/// so the panel keeps its usual look.
+pub fn card_health_style(gpu: &GpuMetrics, health: &CardHealth, t: &Thresholds, theme: &Theme) -> Option<Style> {
+    let sev = health.severity(gpu, t);
+    // Reversed borders turn into solid bars
+    (sev >= Severity::Warn).then(|| theme.style(sev).remove_modifier(Modifier::REVERSED))
+}
+
+fn severity(v: Option<f32>, warn: f32, crit: f32) -> Severity {
+    match v {
+        Some(v) if v >= crit => Severity::Critical,
+        Some(v) if v >= warn => Severity::Warn,
+        Some(_) => Severity::Normal,
+        None => Severity::Unknown,
+    }
+}
+
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeName {
+    #[default]
+    Default,
+    Monochrome,
+    HighContrast,
+}
+
+/// Which hues mean "fine / watch it / trouble", on top of the theme.
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Palette {
+    /// Green / yellow / red.
+    #[default]
+    Standard,
+    /// Blue / orange / white, with `·` / `~` / `!` in front of each value so
+    /// the level never depends on telling hues apart.
+    Colorblind,
+}
+
+/// A reading's look under the current theme: the style to draw it in and
+/// the symbol (if any) that goes in front of its value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cue {
+    pub style: Style,
+    pub mark: &'static str,
+}
+
+impl Cue {
+    /// `text` with the mark in front, for gauge labels and padded columns.
+    pub fn label(&self, text: impl std::fmt::Display) -> String {
+        format!("{}{text}", self.mark)
+    }
+
+    pub fn span(&self, text: impl std::fmt::Display) -> Span<'static> {
+        Span::styled(self.label(text), self.style)
+    }
+}
+
+impl From<Cue> for Style {
+    fn from(cue: Cue) -> Self {
+        cue.style
+    }
+}
+
+/// Every style the UI uses for severity and emphasis, so nothing below picks colors itself.
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    pub normal: Style,
+    pub warn: Style,
+    pub critical: Style,
+    pub unknown: Style,
+    /// Selection borders, informational messages.
+    pub accent: Style,
+    /// One per GPU, cycled: panel titles and gauge borders. Red/yellow/green
+    /// are kept out so they never read as a threshold color.
+    pub gpu_accents: [Style; 4],
+    /// Prefixes for unknown / normal / warn / critical values; empty unless
+    /// the palette asks for them.
+    pub marks: [&'static str; 4],
+}
+
+impl Theme {
+    pub fn new(name: ThemeName) -> Self {
+        match name {
+            ThemeName::Default => Self {
+                normal: Style::default().fg(Color::Green),
+                warn: Style::default().fg(Color::Yellow),
+                critical: Style::default().fg(Color::Red),
+                unknown: Style::default().fg(Color::DarkGray),
+                accent: Style::default().fg(Color::Cyan),
+                gpu_accents: [Color::Cyan, Color::Magenta, Color::Blue, Color::LightMagenta]
+                    .map(|c| Style::default().fg(c)),
+                marks: [""; 4],
+            },
+            // No color at all: usable on dumb terminals and without relying on hue
+            ThemeName::Monochrome => Self {
+                normal: Style::default(),
+                warn: Style::default().add_modifier(Modifier::BOLD),
+                critical: Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED),
+                unknown: Style::default().add_modifier(Modifier::DIM),
+                accent: Style::default().add_modifier(Modifier::BOLD),
+                gpu_accents: [Style::default(); 4],
+                marks: [""; 4],
+            },
+            ThemeName::HighContrast => Self {
+                normal: Style::default().fg(Color::LightGreen).add_modifier(Modifier::BOLD),
+                warn: Style::default().fg(Color::LightYellow).add_modifier(Modifier::BOLD),
+                critical: Style::default()
+                    .fg(Color::White)
+                    .bg(Color::Red)
+                    .add_modifier(Modifier::BOLD),
+                unknown: Style::default().fg(Color::Gray),
+                accent: Style::default().fg(Color::LightCyan).add_modifier(Modifier::BOLD),
+                gpu_accents: [Color::LightCyan, Color::LightMagenta, Color::LightBlue, Color::White]
+                    .map(|c| Style::default().fg(c).add_modifier(Modifier::BOLD)),
+                marks: [""; 4],
+            },
+        }
+    }
+
+    /// Swaps the threshold colors and turns on the value marks. Only the
+    /// hues change, so a bold or reversed critical stays bold or reversed;
+    /// monochrome has no hues and just gets the marks.
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        if palette == Palette::Standard {
+            return self;
+        }
+        self.marks = ["", "·", "~", "!"];
+        if self.normal.fg.is_none() {
+            return self;
+        }
+        self.normal = self.normal.fg(Color::Blue);
+        self.warn = self.warn.fg(Color::Indexed(ORANGE));
+        self.critical = match self.critical.bg {
+            Some(_) => self.critical.fg(Color::White).bg(Color::Indexed(DARK_ORANGE)),
+            None => self.critical.fg(Color::White).add_modifier(Modifier::BOLD),
+        };
+        // Blue is a threshold color now
+        self.gpu_accents = self.gpu_accents.map(|s| match s.fg {
+            Some(Color::Blue | Color::LightBlue) => s.fg(Color::Gray),
+            _ => s,
+        });
+        self
+    }
+
+    /// Stable per device index, so a GPU keeps its color while others come and go.
+    pub fn gpu_accent(&self, idx: usize) -> Style {
+        self.gpu_accents[idx % self.gpu_accents.len()]
+    }
+
+    fn style(&self, sev: Severity) -> Style {
+        match sev {
+            Severity::Unknown => self.unknown,
+            Severity::Normal => self.normal,
+            Severity::Warn => self.warn,
+            Severity::Critical => self.critical,
+        }
+    }
+
+    pub fn cue(&self, sev: Severity) -> Cue {
+        Cue { style: self.style(sev), mark: self.marks[sev as usize] }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::new(ThemeName::Default)
+    }
+}
+
+/// xterm-256 oranges, for terminals without truecolor.
+const ORANGE: u8 = 214;
+const DARK_ORANGE: u8 = 166;
+
+fn fill_style(r: f64, limits: Limits, theme: &Theme) -> Cue {
+    let mut cue = theme.cue(severity(Some(r as f32), limits.warn, limits.crit));
+    // Gauge/sparkline fill is drawn with the fg; reversing it would make the bar vanish
+    cue.style = cue.style.remove_modifier(Modifier::REVERSED);
+    cue
+}
+
+pub fn gauge_style(r: f64, t: &Thresholds, theme: &Theme) -> Cue {
+    fill_style(r, t.gauge, theme)
+}
+
+/// VRAM has its own limits: a full card is a different worry than a busy one.
+pub fn vram_style(r: f64, t: &Thresholds, theme: &Theme) -> Cue {
+    fill_style(r, t.vram, theme)
+}
+
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TempUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+}
+
+impl TempUnit {
+    /// Backends always report Celsius; this is only for display.
+    pub fn convert(self, temp_c: f32) -> f32 {
+        match self {
+            TempUnit::Celsius => temp_c,
+            TempUnit::Fahrenheit => temp_c * 9.0 / 5.0 + 32.0,
+        }
+    }
+
+    pub fn suffix(self) -> &'static str {
+        match self {
+            TempUnit::Celsius => "°C",
+            TempUnit::Fahrenheit => "°F",
+        }
+    }
+
+    pub fn toggled(self) -> Self {
+        match self {
+            TempUnit::Celsius => TempUnit::Fahrenheit,
+            TempUnit::Fahrenheit => TempUnit::Celsius,
+        }
+    }
+}
+
+/// Shared by the three temp styles. `temp` is already in `unit`; limits are in
+/// Celsius and converted so the colors mean the same thing either way.
+fn thermal_style(temp: Option<f32>, unit: TempUnit, limits: Limits, theme: &Theme) -> Cue {
+    theme.cue(severity(temp, unit.convert(limits.warn), unit.convert(limits.crit)))
+}
+
+pub fn temp_style(temp: Option<f32>, unit: TempUnit, t: &Thresholds, theme: &Theme) -> Cue {
+    thermal_style(temp, unit, t.temp, theme)
+}
+
+pub fn power_style(power_w: Option<f32>, t: &Thresholds, theme: &Theme) -> Cue {
+    theme.cue(severity(power_w, t.power.warn, t.power.crit))
+}
+
+pub fn junction_style(temp: Option<f32>, unit: TempUnit, t: &Thresholds, theme: &Theme) -> Cue {
+    thermal_style(temp, unit, t.junction, theme)
+}
+
+pub fn mem_temp_style(temp: Option<f32>, unit: TempUnit, t: &Thresholds, theme: &Theme) -> Cue {
+    thermal_style(temp, unit, t.mem_temp, theme)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legend_shows_the_limits_in_the_current_unit() {
+        let t = Thresholds::default();
+        let legend = legend(&t, TempUnit::Fahrenheit);
+        assert_eq!(legend[0], ("Temp", ["<176 °F".into(), "176–194 °F".into(), "≥194 °F".into()]));
+        assert_eq!(legend[4].1[1], "75–90%");
+        // The bands' edges are exactly where the styling switches
+        let edge = |f: f32| thermal_style(Some(f), TempUnit::Fahrenheit, t.temp, &Theme::default()).style;
+        assert_eq!(edge(194.0), Theme::default().style(Severity::Critical));
+    }
+
+    #[test]
+    fn severity_boundaries_are_inclusive() {
+        assert_eq!(severity(None, 80.0, 90.0), Severity::Unknown);
+        assert_eq!(severity(Some(79.9), 80.0, 90.0), Severity::Normal);
+        assert_eq!(severity(Some(80.0), 80.0, 90.0), Severity::Warn);
+        assert_eq!(severity(Some(90.0), 80.0, 90.0), Severity::Critical);
+    }
+
+    #[test]
+    fn card_health_is_the_worst_chosen_reading() {
+        let t = Thresholds::default();
+        let gpu = GpuMetrics {
+            temperature_c: Some(60.0),
+            junction_temp_c: Some(100.0),
+            power_w: Some(310.0),
+            ..Default::default()
+        };
+        assert_eq!(CardHealth::default().severity(&gpu, &t), Severity::Critical);
+        let temps_only = CardHealth { power: false, ..Default::default() };
+        assert_eq!(temps_only.severity(&gpu, &t), Severity::Warn);
+        let core_only = CardHealth { temp: true, junction: false, mem_temp: false, power: false };
+        assert_eq!(core_only.severity(&gpu, &t), Severity::Normal);
+        assert_eq!(card_health_style(&gpu, &core_only, &t, &Theme::default()), None);
+    }
+
+    #[test]
+    fn fahrenheit_uses_the_same_celsius_limits() {
+        let (t, theme) = (Thresholds::default(), Theme::default());
+        let f = TempUnit::Fahrenheit;
+        // 80°C warn == 176°F
+        assert_eq!(temp_style(Some(f.convert(79.0)), f, &t, &theme).style, theme.normal);
+        assert_eq!(temp_style(Some(f.convert(80.0)), f, &t, &theme).style, theme.warn);
+        assert_eq!(temp_style(Some(f.convert(90.0)), f, &t, &theme).style, theme.critical);
+    }
+
+    #[test]
+    fn temp_unit_round_trips() {
+        assert_eq!(TempUnit::Celsius.convert(100.0), 100.0);
+        assert_eq!(TempUnit::Fahrenheit.convert(100.0), 212.0);
+        assert_eq!(TempUnit::Celsius.toggled().toggled(), TempUnit::Celsius);
+    }
+
+    #[test]
+    fn check_rejects_warn_at_or_above_crit() {
+        assert_eq!(Thresholds::default().check(), Ok(()));
+        let t = Thresholds { temp: Limits { warn: 90.0, crit: 88.0 }, ..Default::default() };
+        assert_eq!(t.check(), Err("temp warn (90) must be below its crit (88)".into()));
+        let t = Thresholds { vram: Limits { warn: 0.8, crit: 0.8 }, ..Default::default() };
+        assert!(t.check().unwrap_err().starts_with("vram"));
+    }
+
+    #[test]
+    fn partial_threshold_tables_keep_the_other_defaults() {
+        let t: Thresholds = toml::from_str("[temp]\nwarn = 75\n\n[power]\ncrit = 350\n").unwrap();
+        let d = Thresholds::default();
+        assert_eq!((t.temp.warn, t.temp.crit), (75.0, d.temp.crit));
+        assert_eq!((t.power.warn, t.power.crit), (d.power.warn, 350.0));
+        assert_eq!((t.vram.warn, t.vram.crit), (d.vram.warn, d.vram.crit));
+        assert_eq!(t.check(), Ok(()));
+    }
+
+    #[test]
+    fn colorblind_palette_marks_values_and_drops_red_and_green() {
+        let t = Thresholds::default();
+        let theme = Theme::default().with_palette(Palette::Colorblind);
+        let cues = [Some(50.0), Some(85.0), Some(95.0)].map(|v| temp_style(v, TempUnit::Celsius, &t, &theme));
+        assert_eq!(cues.map(|c| c.mark), ["·", "~", "!"]);
+        assert!(cues.iter().all(|c| !matches!(c.style.fg, Some(Color::Red | Color::Green | Color::Yellow))));
+        assert_eq!(cues[2].label("95 °C"), "!95 °C");
+        assert_eq!(temp_style(Some(95.0), TempUnit::Celsius, &t, &Theme::default()).label("95 °C"), "95 °C");
+    }
+
+    #[test]
+    fn gauge_style_never_reverses() {
+        let theme = Theme::new(ThemeName::HighContrast);
+        let style = gauge_style(1.0, &Thresholds::default(), &theme).style;
+        assert!(!style.add_modifier.contains(Modifier::REVERSED));
+    }
+}
@@ -0,0 +1,88 @@
+//! Raw mode / alternate screen setup, and getting the terminal back no matter how we exit.
+
+use std::io::{self, Stdout};
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use crossterm::{
+    cursor,
+    event::{DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture},
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+};
+use ratatui::{Terminal, backend::CrosstermBackend};
+
+pub type Tui = Terminal<CrosstermBackend<Stdout>>;
+
+/// Raw mode and the alternate screen are up.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// The TUI's hold on the terminal. Dropping it (on return, `?` or unwinding)
+/// restores the shell, so errors are always printed to a sane screen.
+pub struct TerminalSession {
+    pub terminal: Tui,
+}
+
+impl TerminalSession {
+    /// `focus` asks for focus-in/out events. Terminals that don't do those
+    /// just never send any.
+    pub fn enter(mouse: bool, focus: bool) -> io::Result<Self> {
+        install_panic_hook();
+        enable_raw_mode()?;
+        ACTIVE.store(true, Ordering::SeqCst);
+        // No session to drop yet, so each failure below undoes the steps before it.
+        let mut stdout = io::stdout();
+        if let Err(e) = execute!(stdout, EnterAlternateScreen) {
+            restore();
+            return Err(e);
+        }
+        if mouse && let Err(e) = execute!(stdout, EnableMouseCapture) {
+            restore();
+            return Err(e);
+        }
+        if focus && let Err(e) = execute!(stdout, EnableFocusChange) {
+            restore();
+            return Err(e);
+        }
+        match Terminal::new(CrosstermBackend::new(stdout)) {
+            Ok(terminal) => Ok(Self { terminal }),
+            Err(e) => {
+                restore();
+                Err(e)
+            }
+        }
+    }
+}
+
+impl Drop for TerminalSession {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+/// Best effort and idempotent: it also runs from the panic hook, and
+/// disabling mouse capture or focus events that were never enabled is harmless.
+fn restore() {
+    ACTIVE.store(false, Ordering::SeqCst);
+    let _ = disable_raw_mode();
+    let _ = execute!(io::stdout(), DisableMouseCapture, DisableFocusChange, LeaveAlternateScreen, cursor::Show);
+}
+
+/// Put the terminal back before the default hook prints the panic message,
+/// otherwise it lands on the alternate screen and vanishes.
+///
+/// Only for panics on the thread drawing the UI. One on a background thread
+/// (the sampler reports its own as a failed sample) mustn't pull the
+/// terminal out from under a UI that keeps running, nor print over it.
+fn install_panic_hook() {
+    let default = panic::take_hook();
+    let ui_thread = thread::current().id();
+    panic::set_hook(Box::new(move |info| {
+        if thread::current().id() != ui_thread && ACTIVE.load(Ordering::SeqCst) {
+            return;
+        }
+        restore();
+        default(info);
+    }));
+}
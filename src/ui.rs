@@ -0,0 +1,1563 @@
+//! Everything drawn with ratatui: layout, panels, gauges and history widgets.
+
+use std::time::Instant;
+
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    text::{Line, Span, Text},
+    symbols::{self, Marker},
+    widgets::{
+        Axis, Block, Borders, Chart, Dataset, Clear, Paragraph, Gauge, GraphType, LineGauge, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, Sparkline, Wrap,
+    },
+    style::{Color, Modifier, Style},
+};
+
+use crate::app::{App, GaugeKind, GaugeLabels, GaugeLayout, GpuFilter, GpuHistory, GpuSeparator, NO_GPUS, SortKey, Trend};
+use crate::backend::BackendHealth;
+use crate::metrics::{FanMetrics, GpuMetrics, Vendor, fmt_hms, fmt_opt, fmt_utc_time, fmt_vram, group_digits, pct_ratio, vram_ratio};
+use crate::style::{
+    Severity, card_health_style, gauge_style, junction_style, legend, mem_temp_style, power_style, temp_style,
+    vram_style,
+};
+
+/// Narrower than this and a GPU column gets unreadable, so we stack them instead.
+const MIN_GPU_COLUMN_WIDTH: u16 = 34;
+
+/// Header (with the multi-GPU summary row) + footer + main block borders.
+const CHROME_HEIGHT: u16 = 9;
+const MIN_TERM_WIDTH: u16 = 40;
+/// Enough for the chrome plus one full GPU panel; below this we don't even try.
+const MIN_TERM_HEIGHT: u16 = CHROME_HEIGHT + MIN_GPU_PANEL_HEIGHT;
+
+pub fn ui(f: &mut ratatui::Frame, app: &App) {
+    app.gpu_areas.borrow_mut().clear();
+    app.body_overflow.set((0, 0));
+    let size = f.size();
+    if app.compact {
+        // No header or footer, so what they'd say goes on the last row
+        let rows = size.height.saturating_sub(1);
+        render_compact(f, app, Rect { height: rows, ..size });
+        render_compact_status(f, app, Rect { y: size.y + rows, height: size.height.min(1), ..size });
+        render_overlays(f, app);
+        return;
+    }
+    if size.width < MIN_TERM_WIDTH || size.height < MIN_TERM_HEIGHT {
+        render_too_small(f, app, size);
+        return;
+    }
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(header_height(app)), Constraint::Min(0), Constraint::Length(3)])
+        .split(size);
+
+    render_header(f, app, layout[0]);
+
+    let main = Block::default().borders(Borders::ALL).title("GPU Metrics");
+    f.render_widget(main.clone(), layout[1]);
+
+    // Inner area inside the main block
+    let inner = main.inner(layout[1]);
+    if app.no_gpus() {
+        render_no_gpus(f, app, inner);
+    } else {
+        render_body(f, app, inner);
+    }
+    // Stale numbers shouldn't look live; the header says how old they are
+    if app.stale_for().is_some() {
+        f.buffer_mut().set_style(inner, Style::default().add_modifier(Modifier::DIM));
+    }
+    render_footer(f, app, layout[2]);
+    render_overlays(f, app);
+}
+
+fn render_body(f: &mut ratatui::Frame, app: &App, inner: Rect) {
+    // Split the main inner area into:
+    // - the per-GPU panels
+    // - process list + history widgets at the bottom, as many as fit while
+    //   still leaving the panels room (dropped from the bottom up)
+    type Section = (u16, fn(&mut ratatui::Frame, &App, Rect));
+    let optional: [Section; 6] = [
+        (6, render_processes),
+        (5, render_util_sparkline),
+        (5, render_power_sparkline),
+        (5, render_vram_sparkline),
+        (5, render_clock_sparkline),
+        (8, render_temp_chart),
+    ];
+    let mut budget = inner.height.saturating_sub(MIN_GPU_PANEL_HEIGHT);
+    let sections: Vec<Section> = optional
+        .into_iter()
+        .take_while(|&(h, _)| {
+            let fits = h <= budget;
+            budget = budget.saturating_sub(h);
+            fits
+        })
+        .collect();
+
+    let mut constraints = vec![Constraint::Min(0)];
+    constraints.extend(sections.iter().map(|&(h, _)| Constraint::Length(h)));
+    let inner_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(inner);
+
+    render_gpu_panels(f, app, inner_chunks[0]);
+    for (&(_, render), &area) in sections.iter().zip(inner_chunks.iter().skip(1)) {
+        render(f, app, area);
+    }
+}
+
+/// Instead of a body full of empty gauges and charts.
+fn render_no_gpus(f: &mut ratatui::Frame, app: &App, area: Rect) {
+    let top = area.height.saturating_sub(1) / 2;
+    let line = Rect::new(area.x, area.y + top, area.width, area.height.min(1));
+    f.render_widget(Paragraph::new(NO_GPUS).style(app.theme.warn).alignment(Alignment::Center), line);
+}
+
+fn render_footer(f: &mut ratatui::Frame, app: &App, area: Rect) {
+    let mut footer_spans = vec![Span::raw(format!(
+        "Elapsed: {}   Samples: {}   Interval: target {}ms / actual {}   Energy: {:.2} Wh",
+        fmt_hms(app.start.elapsed()),
+        app.samples_since_reset,
+        app.tick_rate.as_millis(),
+        app.actual_interval().map_or("--".into(), |d| format!("{}ms", d.as_millis())),
+        app.session_energy_wh
+    ))];
+    if app.sort != SortKey::Device {
+        footer_spans.push(Span::raw(format!("   sorted by {}", app.sort.label())));
+    }
+    if let Some(n) = app.top_n {
+        footer_spans.push(Span::raw(format!("   top {n}")));
+    }
+    if app.filter == GpuFilter::Busy {
+        let hidden = app.metrics.len() - app.display_order().len() - app.collapsed().len();
+        footer_spans.push(Span::raw(format!("   busy only ({hidden} hidden)")));
+    }
+    if app.is_mock() {
+        footer_spans.push(Span::raw("   (data is mocked)"));
+    }
+    for (i, health) in app.backend_health().iter().enumerate() {
+        footer_spans.push(Span::raw(if i == 0 { "   " } else { " " }));
+        let style = if health.ok { app.theme.normal } else { app.theme.critical };
+        footer_spans.push(Span::styled(health_text(health), style));
+    }
+    for span in state_spans(app) {
+        footer_spans.push(Span::raw("   "));
+        footer_spans.push(span);
+    }
+    let footer = Paragraph::new(Line::from(footer_spans))
+        .block(Block::default().borders(Borders::ALL).title("Footer"));
+    f.render_widget(footer, area);
+}
+
+/// Paused, the backend status and any flash message: the footer's tail, and
+/// compact mode's status row.
+fn state_spans(app: &App) -> Vec<Span<'static>> {
+    let mut spans = vec![];
+    if app.paused {
+        spans.push(Span::styled(
+            format!(
+                "PAUSED for {} — space to resume",
+                fmt_hms(app.paused_at.map(|t| t.elapsed()).unwrap_or_default())
+            ),
+            app.theme.warn.add_modifier(Modifier::REVERSED),
+        ));
+    }
+    if let Some(status) = &app.status {
+        spans.push(Span::styled(status.clone(), app.theme.critical));
+    }
+    if let Some(flash) = app.active_flash() {
+        let style = if flash.error { app.theme.critical } else { app.theme.accent };
+        spans.push(Span::styled(flash.text.clone(), style));
+    }
+    spans
+}
+
+/// One unbordered line: the header's alert, then `state_spans`, or the
+/// quit/help hint when there's nothing to say.
+fn render_compact_status(f: &mut ratatui::Frame, app: &App, area: Rect) {
+    let hot = hot_gpus(app);
+    let mut spans = vec![];
+    if !hot.is_empty() {
+        spans.push(Span::styled(format!("CRITICAL TEMPERATURE: {}", hot.join(", ")), app.theme.critical));
+    }
+    if let Some(age) = app.stale_for() {
+        spans.push(Span::styled(format!("stale ({}s ago)", age.as_secs()), app.theme.warn));
+    }
+    spans.extend(state_spans(app));
+    let line = if spans.is_empty() {
+        Line::styled("q to quit, ? for help", Style::default().add_modifier(Modifier::DIM))
+    } else {
+        let mut line = Line::default();
+        for (i, span) in spans.into_iter().enumerate() {
+            if i > 0 {
+                line.push_span(Span::raw("   "));
+            }
+            line.push_span(span);
+        }
+        line
+    };
+    f.render_widget(Paragraph::new(line), area);
+}
+
+/// "NVML ✓", or "AMD sysfs ✗ 3s" with the time since its last good sample.
+fn health_text(health: &BackendHealth) -> String {
+    match (health.ok, health.last_ok) {
+        (true, _) => format!("{} ✓", health.name),
+        (false, Some(at)) => format!("{} ✗ {}s", health.name, at.elapsed().as_secs()),
+        (false, None) => format!("{} ✗", health.name),
+    }
+}
+
+fn render_overlays(f: &mut ratatui::Frame, app: &App) {
+    if app.show_alert_log {
+        render_alert_log(f, app);
+    }
+    if app.show_legend {
+        render_legend(f, app);
+    }
+    if app.show_help {
+        render_help(f, app);
+    }
+    if app.quit_prompt {
+        render_quit_prompt(f, app);
+    }
+}
+
+/// Temperature bars in compact mode run from 0 to this (Celsius).
+const COMPACT_TEMP_MAX_C: f32 = 100.0;
+
+/// `--compact` / `c`: no borders or text blocks, one line of colored bars per GPU.
+fn render_compact(f: &mut ratatui::Frame, app: &App, area: Rect) {
+    let (unit, t, th) = (app.temp_unit, &app.thresholds, &app.theme);
+    // "0 name........ " then "U " / " T " / " M " labels and their value columns
+    let fixed = 15 + 2 + 5 + 3 + 6 + 3 + 17;
+    let bar_width = (area.width.saturating_sub(fixed) / 3).clamp(3, 30) as usize;
+    let bar = |ratio: f64, style: Style| {
+        let filled = (ratio.clamp(0.0, 1.0) * bar_width as f64).round() as usize;
+        [
+            Span::styled("█".repeat(filled), style),
+            Span::styled("░".repeat(bar_width - filled), th.unknown),
+        ]
+    };
+
+    if app.no_gpus() {
+        f.render_widget(Paragraph::new(NO_GPUS).style(th.warn), area);
+        return;
+    }
+    let mut lines = vec![];
+    let mut areas = app.gpu_areas.borrow_mut();
+    let mut row = area.y;
+    for (n, i) in app.display_order().into_iter().enumerate() {
+        let gap = if n > 0 { separator_line(app, area.width) } else { None };
+        let needed = 1 + u16::from(gap.is_some());
+        if row + needed > area.bottom() {
+            break;
+        }
+        lines.extend(gap);
+        row += needed;
+        areas.push((Rect::new(area.x, row - 1, area.width, 1), i));
+        let gpu = &app.metrics[i];
+        let name: String = gpu.name.chars().take(12).collect();
+        let mut spans = vec![Span::styled(format!("{} {name:<12} ", app.device_index(i)), th.gpu_accent(i))];
+        if app.is_disconnected(i) {
+            spans.push(Span::styled("disconnected", th.unknown));
+            lines.push(Line::from(spans));
+            continue;
+        }
+
+        let util = pct_ratio(gpu.utilization_pct);
+        let util_cue = gauge_style(util, t, th);
+        spans.push(Span::raw("U "));
+        spans.extend(bar(util, util_cue.style));
+        let util_str = gpu.utilization_pct.map(|u| format!("{u:.0}%")).unwrap_or("--".into());
+        spans.push(Span::raw(format!(" {:>4}", util_cue.label(util_str))));
+
+        let temp = gpu.temperature_c.map(|c| unit.convert(c));
+        let temp_ratio = gpu.temperature_c.map_or(0.0, |c| (c / COMPACT_TEMP_MAX_C) as f64);
+        let temp_cue = temp_style(temp, unit, t, th);
+        spans.push(Span::raw(" T "));
+        spans.extend(bar(temp_ratio, temp_cue.style.remove_modifier(Modifier::REVERSED)));
+        spans.push(Span::raw(format!(" {:>6}", temp_cue.label(format!("{}{}", fmt_dec(temp, app.precision), unit.suffix())))));
+
+        let vram = vram_ratio(gpu.vram_used_mb, gpu.vram_total_mb);
+        spans.push(Span::raw(" M "));
+        let vram_cue = if gpu.vram_total_mb.is_some() { vram_style(vram, t, th) } else { th.cue(Severity::Unknown) };
+        spans.extend(bar(vram, vram_cue.style));
+        spans.push(Span::raw(format!(" {}", vram_cue.label(vram_text(app, gpu.vram_used_mb, gpu.vram_total_mb)))));
+        lines.push(Line::from(spans));
+    }
+    drop(areas);
+    if lines.len() < area.height as usize {
+        lines.extend(collapsed_line(app));
+    }
+    f.render_widget(Paragraph::new(lines), area);
+    if app.stale_for().is_some() {
+        f.buffer_mut().set_style(area, Style::default().add_modifier(Modifier::DIM));
+    }
+}
+
+/// What `app.separator` puts between two one-line GPU rows, if anything.
+fn separator_line(app: &App, width: u16) -> Option<Line<'static>> {
+    match app.separator {
+        GpuSeparator::None => None,
+        GpuSeparator::Blank => Some(Line::raw("")),
+        GpuSeparator::Rule => Some(Line::styled("─".repeat(width as usize), app.theme.unknown)),
+    }
+}
+
+fn render_quit_prompt(f: &mut ratatui::Frame, app: &App) {
+    let size = f.size();
+    let (w, h) = (30.min(size.width), 3.min(size.height));
+    let area = Rect::new(size.x + (size.width - w) / 2, size.y + (size.height - h) / 2, w, h);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Quit")
+        .border_style(app.theme.accent);
+    f.render_widget(Clear, area);
+    f.render_widget(Paragraph::new("Quit gtop? (y/n)").block(block), area);
+}
+
+/// `a`: recent alerts, newest first, as many as fit.
+fn render_alert_log(f: &mut ratatui::Frame, app: &App) {
+    let lines: Vec<Line> = if app.alert_log.is_empty() {
+        vec![Line::styled("no alerts yet", app.theme.unknown)]
+    } else {
+        app.alert_log
+            .iter()
+            .rev()
+            .map(|event| {
+                let style = if event.critical { app.theme.critical } else { app.theme.normal };
+                Line::from(vec![
+                    Span::raw(format!("{}  ", fmt_utc_time(event.at))),
+                    Span::styled(event.text.clone(), style),
+                ])
+            })
+            .collect()
+    };
+    let area = centered_rect(60, 60, f.size());
+    let log = Paragraph::new(Text::from(lines))
+        .block(Block::default().borders(Borders::ALL).title("Alert log (UTC) — a to close"));
+    f.render_widget(Clear, area);
+    f.render_widget(log, area);
+}
+
+/// `l`: what each color means for each reading, in the current unit and theme.
+fn render_legend(f: &mut ratatui::Frame, app: &App) {
+    let th = &app.theme;
+    let severities = [Severity::Normal, Severity::Warn, Severity::Critical];
+    let mut lines: Vec<Line> = legend(&app.thresholds, app.temp_unit)
+        .into_iter()
+        .map(|(reading, bands)| {
+            let mut spans = vec![Span::styled(format!("{reading:>9}  "), th.accent)];
+            for (band, sev) in bands.into_iter().zip(severities) {
+                spans.push(th.cue(sev).span(band));
+                spans.push(Span::raw("  "));
+            }
+            Line::from(spans)
+        })
+        .collect();
+    lines.push(Line::from(vec![
+        Span::styled(format!("{:>9}  ", "no data"), th.accent),
+        th.cue(Severity::Unknown).span("--"),
+    ]));
+    let area = centered_rect(60, 40, f.size());
+    let legend = Paragraph::new(Text::from(lines))
+        .block(Block::default().borders(Borders::ALL).title("Color legend — l to close"));
+    f.render_widget(Clear, area);
+    f.render_widget(legend, area);
+}
+
+/// Every keybinding, in the order they're shown in the help overlay.
+const KEYBINDINGS: &[(&str, &str)] = &[
+    ("q / Ctrl-C", "close help / alert log, or quit"),
+    ("Esc", "quit immediately"),
+    ("space", "pause / resume sampling"),
+    ("f", "toggle °C / °F"),
+    ("← / → / Tab", "select GPU"),
+    ("e", "reset the session energy counter"),
+    ("r", "reset histories, averages, peaks and energy"),
+    ("c", "toggle compact bar view"),
+    ("b", "cycle nothing / blank line / rule between GPU rows"),
+    ("d", "cycle 0 / 1 / 2 decimals for temps and power"),
+    ("o", "sort: device / temp / util / VRAM / power"),
+    ("n / N", "show one fewer / more of the top GPUs"),
+    ("1-5", "show / hide util, VRAM, fan, power, clock gauges"),
+    ("g", "gauge readings on the bar / in the title / off"),
+    ("↑↓ PgUp PgDn", "scroll GPU panel text that doesn't fit"),
+    ("j / k", "scroll process list"),
+    ("a", "show / hide the alert log"),
+    ("l", "show / hide the color legend"),
+    ("m", "raw / smoothed utilization"),
+    ("s", "write JSON snapshot"),
+    ("x", "save screen as .ans + .svg"),
+    ("[ / ]", "shrink / grow history window"),
+    ("+ / -", "sample faster / slower"),
+    ("mouse", "click selects GPU, wheel scrolls (--mouse)"),
+    ("?", "toggle this help"),
+];
+
+/// Floating help box drawn over whatever is already on screen.
+fn render_help(f: &mut ratatui::Frame, app: &App) {
+    let mut lines: Vec<Line> = KEYBINDINGS
+        .iter()
+        .map(|(key, what)| {
+            Line::from(vec![
+                Span::styled(format!("{key:>12}  "), app.theme.accent),
+                Span::raw(*what),
+            ])
+        })
+        .collect();
+    // Panel titles may have had to shorten it
+    if let Some(gpu) = app.metrics.get(app.selected) {
+        lines.push(Line::raw(""));
+        lines.push(Line::from(vec![
+            Span::styled(format!("{:>12}  ", "selected"), app.theme.accent),
+            Span::raw(format!("GPU {}: {}", app.device_index(app.selected), gpu.name)),
+        ]));
+    }
+
+    let area = centered_rect(60, 60, f.size());
+    let help = Paragraph::new(Text::from(lines))
+        .block(Block::default().borders(Borders::ALL).title("Help — ? to close"));
+    f.render_widget(Clear, area);
+    f.render_widget(help, area);
+}
+
+/// `percent_x` by `percent_y` of `r`, centered.
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(vertical[1])[1]
+}
+
+/// Below this a stacked GPU panel can't show its gauges plus a few text lines.
+const MIN_GPU_PANEL_HEIGHT: u16 = 15;
+
+fn render_too_small(f: &mut ratatui::Frame, app: &App, size: Rect) {
+    let msg = format!(
+        "Terminal too small ({}x{}), need at least {MIN_TERM_WIDTH}x{MIN_TERM_HEIGHT}",
+        size.width, size.height
+    );
+    let text = Paragraph::new(msg)
+        .style(app.theme.warn)
+        .wrap(Wrap { trim: true });
+    f.render_widget(text, size);
+}
+
+/// Full-screen replacement for the whole UI when gtop couldn't start
+/// (no `App` exists yet, so no theme either).
+pub fn render_error(f: &mut ratatui::Frame, message: &str) {
+    let size = f.size();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("gtop failed to start")
+        .border_style(Style::default().fg(Color::Red));
+    let text = Text::from(vec![
+        Line::from(message.to_string()),
+        Line::from(""),
+        Line::styled("press any key to exit", Style::default().add_modifier(Modifier::DIM)),
+    ]);
+    f.render_widget(Paragraph::new(text).block(block).wrap(Wrap { trim: true }), size);
+}
+
+/// "GPU 1 93.0°C" for each GPU over the alert temp.
+fn hot_gpus(app: &App) -> Vec<String> {
+    app.alerting
+        .iter()
+        .zip(&app.metrics)
+        .enumerate()
+        .filter(|(_, (alerting, _))| **alerting)
+        .map(|(i, (_, gpu))| {
+            let t = gpu.temperature_c.map(|t| app.temp_unit.convert(t));
+            format!("GPU {} {}{}", app.device_index(i), fmt_dec(t, app.precision), app.temp_unit.suffix())
+        })
+        .collect()
+}
+
+/// Normally just the title/hint line. While any GPU is over the alert temp the
+/// whole header turns critical and blinks (by flipping reverse every tick).
+fn render_header(f: &mut ratatui::Frame, app: &App, area: Rect) {
+    let hot = hot_gpus(app);
+    let block = Block::default().borders(Borders::ALL);
+    let (block, mut text) = if hot.is_empty() {
+        let text = Line::from(format!("{} — q to quit, ? for help", header_title(app)));
+        (block.title("Header"), text)
+    } else {
+        let mut style = app.theme.critical.remove_modifier(Modifier::REVERSED);
+        if app.tick.is_multiple_of(2) {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+        let text = Line::styled(format!("CRITICAL TEMPERATURE: {}", hot.join(", ")), style);
+        (block.title("ALERT").border_style(app.theme.critical), text)
+    };
+    if let Some(age) = app.stale_for() {
+        text.push_span(Span::raw("   "));
+        text.push_span(Span::styled(format!("stale ({}s ago)", age.as_secs()), app.theme.warn));
+    }
+    let mut lines = vec![text];
+    lines.extend(summary_groups(app).into_iter().map(|(label, gpus)| rig_summary_line(app, label, &gpus)));
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// Totals for the GPUs `--top` / `n` left out, if it left any out.
+fn collapsed_line(app: &App) -> Option<Line<'static>> {
+    let rest = app.collapsed();
+    if rest.is_empty() {
+        return None;
+    }
+    let gpus: Vec<&GpuMetrics> = rest.iter().map(|&i| &app.metrics[i]).collect();
+    Some(rig_summary_line(app, format!("{} more", gpus.len()), &gpus))
+}
+
+/// Borders plus the title line, and the rig summary rows once there's more than one GPU.
+fn header_height(app: &App) -> u16 {
+    3 + summary_groups(app).len() as u16
+}
+
+/// What the header sums up: every connected GPU together, then each vendor
+/// on its own when there's more than one.
+fn summary_groups(app: &App) -> Vec<(String, Vec<&GpuMetrics>)> {
+    if app.metrics.len() < 2 {
+        return vec![];
+    }
+    let gpus: Vec<&GpuMetrics> = app
+        .metrics
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| !app.is_disconnected(i))
+        .map(|(_, gpu)| gpu)
+        .collect();
+    let plural = |n: usize| if n == 1 { "" } else { "s" };
+    let mut vendors: Vec<Vendor> = gpus.iter().map(|g| g.vendor).collect();
+    vendors.sort_unstable();
+    vendors.dedup();
+    let mut groups = vec![(format!("All {} GPU{}", gpus.len(), plural(gpus.len())), gpus.clone())];
+    if vendors.len() > 1 {
+        groups.extend(vendors.into_iter().map(|vendor| {
+            let own: Vec<&GpuMetrics> = gpus.iter().copied().filter(|g| g.vendor == vendor).collect();
+            (format!("{} {} GPU{}", vendor.label(), own.len(), plural(own.len())), own)
+        }));
+    }
+    groups
+}
+
+/// Totals across a group of GPUs. A card that doesn't report a reading is
+/// left out of that total, and the total says how many cards it covers.
+fn rig_summary_line(app: &App, label: String, gpus: &[&GpuMetrics]) -> Line<'static> {
+    let n = gpus.len();
+    let mut spans = vec![Span::raw(format!("{label}: "))];
+    let mut push = |label: &str, value: Option<String>, reported: usize| {
+        if spans.len() > 1 {
+            spans.push(Span::raw(" | "));
+        }
+        spans.push(Span::raw(format!("{label} {}", value.unwrap_or("--".into()))));
+        if reported < n {
+            spans.push(Span::styled(format!(" ({reported}/{n})"), app.theme.unknown));
+        }
+    };
+
+    let used: Vec<u32> = gpus.iter().filter_map(|g| g.vram_used_mb).collect();
+    let total: Vec<u32> = gpus.iter().filter_map(|g| g.vram_used_mb.and(g.vram_total_mb)).collect();
+    let vram = (!used.is_empty()).then(|| {
+        let total = (total.len() == used.len()).then(|| total.iter().sum());
+        vram_text(app, Some(used.iter().sum()), total)
+    });
+    push("VRAM", vram, used.len());
+
+    let util: Vec<f32> = gpus.iter().filter_map(|g| g.utilization_pct).collect();
+    let avg = (!util.is_empty()).then(|| format!("{:.0}%", util.iter().sum::<f32>() / util.len() as f32));
+    push("avg util", avg, util.len());
+
+    let power: Vec<f32> = gpus.iter().filter_map(|g| g.power_w).collect();
+    let sum = (!power.is_empty()).then(|| format!("{} W", fmt_dec(Some(power.iter().sum()), app.precision)));
+    push("power", sum, power.len());
+
+    Line::from(spans)
+}
+
+/// "gtop — AMD sysfs — hostname — 2 GPUs"
+fn header_title(app: &App) -> String {
+    let mut parts = vec!["gtop".to_string(), app.source().to_string()];
+    parts.extend(app.host.clone());
+    let n = app.metrics.len();
+    parts.push(format!("{n} GPU{}", if n == 1 { "" } else { "s" }));
+    parts.join(" — ")
+}
+
+/// Every GPU gets a full panel (side by side, or stacked) when they all fit.
+/// Otherwise only the selected one is detailed and the rest get a one-line summary.
+fn render_gpu_panels(f: &mut ratatui::Frame, app: &App, area: Rect) {
+    let area = match collapsed_line(app) {
+        Some(line) if area.height > 1 => {
+            let bottom = Rect::new(area.x, area.bottom() - 1, area.width, 1);
+            f.render_widget(Paragraph::new(line), bottom);
+            Rect { height: area.height - 1, ..area }
+        }
+        _ => area,
+    };
+    let order = app.display_order();
+    let n = order.len();
+    if n == 0 {
+        if !app.metrics.is_empty() {
+            let msg = format!("all GPUs idle (under {:.0}% utilization)", app.busy_threshold);
+            f.render_widget(Paragraph::new(msg).style(app.theme.unknown), area);
+        }
+        return;
+    }
+
+    let direction = if area.width / n as u16 >= MIN_GPU_COLUMN_WIDTH {
+        Some(Direction::Horizontal)
+    } else if area.height / n as u16 >= MIN_GPU_PANEL_HEIGHT {
+        Some(Direction::Vertical)
+    } else {
+        None
+    };
+
+    let Some(direction) = direction else {
+        render_focused_gpu(f, app, &order, area);
+        return;
+    };
+
+    let areas = Layout::default()
+        .direction(direction)
+        .constraints(vec![Constraint::Ratio(1, n as u32); n])
+        .split(area);
+
+    for (&i, area) in order.iter().zip(areas.iter()) {
+        render_gpu(f, app, i, &app.metrics[i], *area);
+    }
+}
+
+/// Summary lines for the non-selected GPUs on top, full panel for the selected one below.
+fn render_focused_gpu(f: &mut ratatui::Frame, app: &App, order: &[usize], area: Rect) {
+    // Each line's GPU, `None` for separators
+    let mut rows: Vec<Option<usize>> = vec![];
+    let mut others: Vec<Line> = vec![];
+    for &i in order.iter().filter(|&&i| i != app.selected) {
+        if !others.is_empty() && let Some(gap) = separator_line(app, area.width) {
+            rows.push(None);
+            others.push(gap);
+        }
+        rows.push(Some(i));
+        others.push(gpu_summary_line(app, i, &app.metrics[i]));
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(others.len() as u16), Constraint::Min(0)])
+        .split(area);
+
+    let mut areas = app.gpu_areas.borrow_mut();
+    for (row, i) in (chunks[0].y..chunks[0].bottom()).zip(rows) {
+        if let Some(i) = i {
+            areas.push((Rect::new(chunks[0].x, row, chunks[0].width, 1), i));
+        }
+    }
+    drop(areas);
+    f.render_widget(Paragraph::new(Text::from(others)), chunks[0]);
+    if let Some(gpu) = app.metrics.get(app.selected) {
+        render_gpu(f, app, app.selected, gpu, chunks[1]);
+    }
+}
+
+/// `name` cut to `width` characters, ending in "…" if anything was dropped.
+fn truncate_name(name: &str, width: usize) -> String {
+    if name.chars().count() <= width {
+        return name.to_string();
+    }
+    let kept: String = name.chars().take(width.saturating_sub(1)).collect();
+    format!("{}…", kept.trim_end())
+}
+
+/// Text block + utilization, VRAM and fan gauges for a single GPU.
+fn render_gpu(f: &mut ratatui::Frame, app: &App, idx: usize, gpu: &GpuMetrics, area: Rect) {
+    app.gpu_areas.borrow_mut().push((area, idx));
+    let accent = app.theme.gpu_accent(idx);
+    // Titles don't wrap, they just get cut off; `?` shows the full name
+    let prefix = match gpu.vendor {
+        Vendor::Unknown => format!("GPU {}: ", app.device_index(idx)),
+        vendor => format!("GPU {} [{}]: ", app.device_index(idx), vendor.label()),
+    };
+    let room = (area.width as usize).saturating_sub(2 + prefix.chars().count());
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .title(Span::styled(format!("{prefix}{}", truncate_name(&gpu.name, room)), accent));
+    let selected = idx == app.selected && app.metrics.len() > 1;
+    // A card in trouble shows it even when selected; bold still marks the selection
+    match card_health_style(gpu, &app.card_health, &app.thresholds, &app.theme) {
+        Some(health) if selected => block = block.border_style(health.add_modifier(Modifier::BOLD)),
+        Some(health) => block = block.border_style(health),
+        None if selected => block = block.border_style(app.theme.accent),
+        None => {}
+    }
+    if let Some(Some(since)) = app.disconnected.get(idx) {
+        let text = format!("disconnected — last seen {}s ago", since.elapsed().as_secs());
+        let block = block.border_style(app.theme.unknown);
+        f.render_widget(Paragraph::new(text).style(app.theme.unknown).block(block), area);
+        return;
+    }
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    // Hidden gauges (`1`-`5`) don't get a row; the text block takes the space.
+    let shown = |kind| app.gauges.contains(kind);
+    let util = shown(GaugeKind::Util).then(|| util_gauge(app, accent, idx));
+    // Memory controller load sits next to capacity, so the two read as different things
+    let vram: Vec<Gauge> = [Some(vram_gauge(app, accent, gpu)), mem_busy_gauge(app, accent, gpu)]
+        .into_iter()
+        .flatten()
+        .filter(|_| shown(GaugeKind::Vram))
+        .collect();
+    let fans = if shown(GaugeKind::Fan) { fan_gauges(app, accent, gpu) } else { vec![] };
+    let power = shown(GaugeKind::Power).then(|| power_gauge(app, accent, gpu));
+    // Clock gauges only when there's a max to scale against. Stacked, power
+    // and clocks also need the text block to keep a few lines; they're in
+    // the text either way.
+    let clocks: Vec<Gauge> = [
+        clock_gauge(app, accent, "Core Clock", gpu.core_clock_mhz, gpu.core_clock_max_mhz),
+        clock_gauge(app, accent, "Mem Clock", gpu.mem_clock_mhz, gpu.mem_clock_max_mhz),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|_| shown(GaugeKind::Clocks))
+    .collect();
+    // A breakdown of one engine would just repeat the utilization gauge
+    let engines = &gpu.engine_utilization;
+    let mut engine_height = if shown(GaugeKind::Util) && engines.len() > 1 { engines.len() as u16 + 2 } else { 0 };
+    let fits = |rows: usize, extra: u16| inner.height >= MIN_TEXT_LINES + 3 * rows as u16 + extra;
+    let mut rows: Vec<Vec<Gauge>> = if gauges_side_by_side(f, app) {
+        let mut second = fans;
+        second.extend(power);
+        second.extend(clocks);
+        vec![util.into_iter().chain(vram).collect(), second]
+    } else {
+        let mut rows = vec![Vec::from_iter(util), vram, fans];
+        rows.retain(|row| !row.is_empty());
+        // Engines go ahead of power and clocks, which the text has anyway
+        if !fits(rows.len(), engine_height) {
+            engine_height = 0;
+        }
+        for optional in [Vec::from_iter(power), clocks] {
+            if !optional.is_empty() && fits(rows.len() + 1, engine_height) {
+                rows.push(optional);
+            }
+        }
+        rows
+    };
+    rows.retain(|row| !row.is_empty());
+    let show_engines = engine_height > 0 && fits(rows.len(), engine_height);
+
+    let mut constraints = vec![Constraint::Min(0)];
+    constraints.extend(std::iter::repeat_n(Constraint::Length(3), rows.len()));
+    // Right under the first row, which the utilization gauge starts
+    if show_engines {
+        constraints.insert(2, Constraint::Length(engine_height));
+    }
+    let mut chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(inner)
+        .to_vec();
+    if show_engines {
+        render_engine_gauges(f, app, accent, engines, chunks.remove(2));
+    }
+
+    render_gpu_text(f, app, gpu_lines(app, idx, gpu), chunks[0]);
+    for (row, &area) in rows.into_iter().zip(chunks.iter().skip(1)) {
+        let n = row.len() as u32;
+        let cols = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Ratio(1, n); n as usize])
+            .split(area);
+        for (gauge, &col) in row.into_iter().zip(cols.iter()) {
+            f.render_widget(gauge, col);
+        }
+    }
+}
+
+/// One thin gauge per engine, so the one holding the card back stands out.
+fn render_engine_gauges(f: &mut ratatui::Frame, app: &App, accent: Style, engines: &[(String, f32)], area: Rect) {
+    let block = gauge_block("Engines", accent);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let lines = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Constraint::Length(1); engines.len()])
+        .split(inner);
+    let width = engines.iter().map(|(name, _)| name.chars().count()).max().unwrap_or(0);
+    for ((name, pct), &line) in engines.iter().zip(lines.iter()) {
+        let ratio = pct_ratio(Some(*pct));
+        let cue = gauge_style(ratio, &app.thresholds, &app.theme);
+        let label = match app.gauge_labels {
+            GaugeLabels::Off => format!("{name:<width$}"),
+            _ => format!("{name:<width$} {pct:>3.0}%"),
+        };
+        let gauge = LineGauge::default().ratio(ratio).label(cue.label(label)).gauge_style(cue.style);
+        f.render_widget(gauge.line_set(symbols::line::THICK), line);
+    }
+}
+
+/// A panel's text block, scrolled by `app.body_scroll` when it doesn't fit,
+/// with a scrollbar over the panel's right border beside it.
+fn render_gpu_text(f: &mut ratatui::Frame, app: &App, lines: Vec<Line>, area: Rect) {
+    let overflow = lines.len().saturating_sub(area.height as usize);
+    if overflow > app.body_overflow.get().0 {
+        app.body_overflow.set((overflow, area.height as usize));
+    }
+    let scroll = app.body_scroll.min(overflow);
+    f.render_widget(Paragraph::new(Text::from(lines)).scroll((scroll as u16, 0)), area);
+    if overflow > 0 {
+        let track = Rect::new(area.right(), area.y, 1, area.height);
+        let mut scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .track_symbol(Some("│"))
+            .thumb_style(app.theme.accent);
+        // Arrow heads would leave a two-line track no room for the thumb
+        if area.height < 4 {
+            scrollbar = scrollbar.begin_symbol(None).end_symbol(None);
+        }
+        let mut state = ScrollbarState::new(overflow + 1).position(scroll).viewport_content_length(area.height as usize);
+        f.render_stateful_widget(scrollbar, track, &mut state);
+    }
+}
+
+/// `auto` goes side by side once the terminal is at least this many times
+/// wider than it is tall (cells are roughly twice as tall as they are wide).
+const AUTO_HORIZONTAL_ASPECT: u16 = 4;
+
+fn gauges_side_by_side(f: &ratatui::Frame, app: &App) -> bool {
+    match app.layout {
+        GaugeLayout::Vertical => false,
+        GaugeLayout::Horizontal => true,
+        GaugeLayout::Auto => {
+            let size = f.size();
+            size.width >= size.height.saturating_mul(AUTO_HORIZONTAL_ASPECT)
+        }
+    }
+}
+
+/// Lines of text a GPU panel keeps before optional gauges are given up.
+const MIN_TEXT_LINES: u16 = 4;
+
+/// Integer digits the panel text leaves room for, so a reading keeps its
+/// column as it goes from 9 to 99 to 100 (or 950 to 1000 MHz).
+const TEMP_DIGITS: usize = 3;
+const POWER_DIGITS: usize = 4;
+const CLOCK_DIGITS: usize = 4;
+const RPM_DIGITS: usize = 4;
+
+/// Right-aligned to `int_digits` plus `precision` decimals. Wider values
+/// (a 1200 W reading) just push the line over rather than being cut.
+fn fixed(text: String, int_digits: usize, precision: usize) -> String {
+    let width = int_digits + if precision > 0 { precision + 1 } else { 0 };
+    format!("{text:>width$}")
+}
+
+/// `fixed` for whole numbers, after `[ui] thousands_separator` grouping.
+fn fixed_int(app: &App, text: String, digits: usize) -> String {
+    let width = digits + app.thousands_sep.map_or(0, |_| (digits - 1) / 3);
+    format!("{:>width$}", grouped(app, text))
+}
+
+/// Temps and power share the `d` / `[ui] precision` setting.
+pub fn fmt_dec(v: Option<f32>, precision: usize) -> String {
+    v.map(|v| format!("{v:.precision$}")).unwrap_or("--".into())
+}
+
+/// e.g. `GPU 1: RTX 4090 | 61.0 °C | 35% | 1229 / 16384 MB | 99 W`
+fn gpu_summary_line(app: &App, idx: usize, gpu: &GpuMetrics) -> Line<'static> {
+    let (unit, t, th) = (app.temp_unit, &app.thresholds, &app.theme);
+    if app.is_disconnected(idx) {
+        return Line::styled(format!("GPU {}: {} | disconnected", app.device_index(idx), gpu.name), th.unknown);
+    }
+    let temp = gpu.temperature_c.map(|t| unit.convert(t));
+    let temp_str = fmt_dec(temp, app.precision);
+    let util_str = gpu.utilization_pct.map(|u| format!("{u:.0}%")).unwrap_or("--".into());
+    let power_str = fmt_dec(gpu.power_w, app.precision);
+    Line::from(vec![
+        Span::styled(format!("GPU {}: {}", app.device_index(idx), gpu.name), th.gpu_accent(idx)),
+        Span::raw(" | "),
+        temp_style(temp, unit, t, th).span(format!("{temp_str} {}", unit.suffix())),
+        trend_arrow(app, idx),
+        Span::raw(" | "),
+        gauge_style(pct_ratio(gpu.utilization_pct), t, th).span(util_str),
+        Span::raw(format!(" | {} | ", vram_text(app, gpu.vram_used_mb, gpu.vram_total_mb))),
+        power_style(gpu.power_w, t, th).span(format!("{power_str} W")),
+    ])
+}
+
+/// ` ▲` / ` ▼` / ` ▬` after the core temp; a dimmed ` ▬` until there's a previous sample.
+fn trend_arrow(app: &App, idx: usize) -> Span<'static> {
+    let th = &app.theme;
+    match app.histories.get(idx).and_then(|h| h.temp_trend) {
+        Some(Trend::Rising) => Span::styled(" ▲", th.warn),
+        Some(Trend::Falling) => Span::styled(" ▼", th.normal),
+        Some(Trend::Steady) => Span::raw(" ▬"),
+        None => Span::styled(" ▬", th.unknown),
+    }
+}
+
+fn gpu_lines(app: &App, idx: usize, gpu: &GpuMetrics) -> Vec<Line<'static>> {
+    let (unit, t, th) = (app.temp_unit, &app.thresholds, &app.theme);
+    // Temps come in as Celsius; convert once for display + styling
+    let fmt_temp = |t: Option<f32>| {
+        format!("{} {}", fixed(fmt_dec(t, app.precision), TEMP_DIGITS, app.precision), unit.suffix())
+    };
+    let fmt_power = |w: Option<f32>| fixed(fmt_dec(w, app.precision), POWER_DIGITS, app.precision);
+    let temp = gpu.temperature_c.map(|t| unit.convert(t));
+    let junction = gpu.junction_temp_c.map(|t| unit.convert(t));
+    let mem_temp = gpu.mem_temp_c.map(|t| unit.convert(t));
+    let power_str = fmt_power(gpu.power_w);
+    let history = app.histories.get(idx);
+    let power_avg = history.and_then(GpuHistory::power_avg);
+    let power_peak = history.and_then(|h| h.power_peak);
+    let power_stats = format!(" (avg {}, peak {})", fmt_power(power_avg), fmt_power(power_peak));
+
+    let mut lines = vec![];
+    if !gpu.throttle_reasons.is_empty() {
+        let text = format!("THROTTLING: {}", gpu.throttle_reasons.join(", "));
+        lines.push(Line::styled(text, th.critical.add_modifier(Modifier::BOLD)));
+    }
+    lines.extend([
+        Line::from(vec![
+            Span::raw("Temp: "),
+            temp_style(temp, unit, t, th).span(fmt_temp(temp)),
+            trend_arrow(app, idx),
+        ]),
+        Line::from(vec![
+            Span::raw("Junction: "),
+            junction_style(junction, unit, t, th).span(fmt_temp(junction)),
+        ]),
+        Line::from(vec![
+            Span::raw("Mem Temp: "),
+            mem_temp_style(mem_temp, unit, t, th).span(fmt_temp(mem_temp)),
+        ]),
+        Line::from(vec![
+            // Named for what it is once there's a second reading to tell it from
+            Span::raw(if gpu.gpu_chip_power_w.is_some() { "Board power: " } else { "Power: " }),
+            power_style(gpu.power_w, t, th).span(format!("{power_str} W")),
+            Span::raw(power_stats),
+        ]),
+    ]);
+    if let Some(chip) = gpu.gpu_chip_power_w {
+        let chip = fixed(fmt_dec(Some(chip), app.precision), POWER_DIGITS, app.precision);
+        lines.push(Line::from(format!("Chip power: {chip} W")));
+    }
+    if let Some(busy) = gpu.mem_utilization_pct {
+        lines.push(Line::from(vec![
+            Span::raw("Mem Busy: "),
+            gauge_style(pct_ratio(Some(busy)), t, th).span(format!("{:>3}%", format!("{busy:.0}"))),
+            Span::raw(" (controller, not capacity)"),
+        ]));
+    }
+    lines.extend([
+        Line::from(format!(
+            "Clocks: core {} MHz | mem {} MHz",
+            fixed_int(app, fmt_opt(&gpu.core_clock_mhz), CLOCK_DIGITS),
+            fixed_int(app, fmt_opt(&gpu.mem_clock_mhz), CLOCK_DIGITS),
+        )),
+    ]);
+    if let Some(state) = &gpu.perf_state {
+        lines.push(Line::from(format!("Perf state: {state}")));
+    }
+    lines.extend([
+        Line::from(fan_line(app, gpu)),
+        Line::from(format!("PCIe: {}", fmt_pcie(gpu.pcie_gen, gpu.pcie_width))),
+    ]);
+    if let Some(errors) = gpu.ecc_errors {
+        let style = if errors > 0 { th.critical } else { th.normal };
+        lines.push(Line::from(vec![Span::raw("ECC errors: "), Span::styled(format!("{errors} uncorrected"), style)]));
+    }
+    lines.extend([
+        Line::from(format!(
+            "Driver: {} | VBIOS: {}",
+            gpu.driver_version.as_deref().unwrap_or("--"),
+            gpu.vbios_version.as_deref().unwrap_or("--"),
+        )),
+    ]);
+    lines
+}
+
+/// "4.0 x16", with `--` for whichever half the backend couldn't read.
+fn fmt_pcie(generation: Option<u8>, width: Option<u8>) -> String {
+    let generation = generation.map(|g| format!("{g}.0")).unwrap_or("--".into());
+    let width = width.map(|w| format!("x{w}")).unwrap_or("x--".into());
+    format!("{generation} {width}")
+}
+
+/// Gauge frame in the GPU's accent; the fill keeps its threshold color.
+fn gauge_block(title: &str, accent: Style) -> Block<'static> {
+    Block::default().borders(Borders::ALL).title(title.to_string()).border_style(accent)
+}
+
+/// Frames `gauge` and puts its reading where `app.gauge_labels` says: on the
+/// bar, in place of the frame title (every label names its metric), or nowhere.
+fn labeled(
+    app: &App,
+    gauge: Gauge<'static>,
+    title: &str,
+    accent: Style,
+    label: impl Into<Span<'static>>,
+) -> Gauge<'static> {
+    // Without a label of its own the gauge would print its percentage
+    let none = Span::raw("");
+    match app.gauge_labels {
+        GaugeLabels::Bar => gauge.block(gauge_block(title, accent)).label(label),
+        GaugeLabels::Title => {
+            let block = Block::default().borders(Borders::ALL).title(label.into()).border_style(accent);
+            gauge.block(block).label(none)
+        }
+        GaugeLabels::Off => gauge.block(gauge_block(title, accent)).label(none),
+    }
+}
+
+fn util_gauge(app: &App, accent: Style, idx: usize) -> Gauge<'static> {
+    let util = app.shown_util(idx);
+    let ratio = pct_ratio(util);
+    let suffix = if app.smooth_util { format!(" (avg of {})", app.smoothing) } else { String::new() };
+    let label = util
+        .map(|u| format!("GPU Util {u:.0}%{suffix}"))
+        .unwrap_or_else(|| "GPU Util --".into());
+    let cue = gauge_style(ratio, &app.thresholds, &app.theme);
+    let gauge = Gauge::default().gauge_style(cue).ratio(ratio);
+    labeled(app, gauge, "Utilization", accent, cue.label(label))
+}
+
+/// Without a total (unified memory, some iGPUs) there's nothing to fill
+/// against, so the gauge goes gray and empty instead of looking like 0% used.
+fn vram_gauge(app: &App, accent: Style, gpu: &GpuMetrics) -> Gauge<'static> {
+    if gpu.vram_total_mb.is_none() {
+        // There's room to spell out fmt_vram's "800 MB / ?" here
+        let label = vram_text(app, gpu.vram_used_mb, None).replace("/ ?", "/ unknown");
+        let gauge = Gauge::default().gauge_style(app.theme.unknown).ratio(0.0);
+        return labeled(app, gauge, "VRAM Usage", accent, Span::styled(format!("VRAM {label}"), app.theme.unknown));
+    }
+    let ratio = vram_ratio(gpu.vram_used_mb, gpu.vram_total_mb);
+    let label = format!("VRAM {}", vram_text(app, gpu.vram_used_mb, gpu.vram_total_mb));
+    let cue = vram_style(ratio, &app.thresholds, &app.theme);
+    labeled(app, Gauge::default().gauge_style(cue).ratio(ratio), "VRAM Usage", accent, cue.label(label))
+}
+
+/// Only for backends that report it; `None` leaves VRAM its whole row.
+fn mem_busy_gauge(app: &App, accent: Style, gpu: &GpuMetrics) -> Option<Gauge<'static>> {
+    let busy = gpu.mem_utilization_pct?;
+    let ratio = pct_ratio(Some(busy));
+    let cue = gauge_style(ratio, &app.thresholds, &app.theme);
+    let gauge = Gauge::default().gauge_style(cue).ratio(ratio);
+    Some(labeled(app, gauge, "Memory Busy", accent, cue.label(format!("Mem Busy {busy:.0}%"))))
+}
+
+/// Scale for the power gauge when the backend doesn't know the card's limit.
+const POWER_GAUGE_FALLBACK_W: f32 = 400.0;
+
+/// Draw against the power limit, i.e. headroom before power throttling.
+fn power_gauge(app: &App, accent: Style, gpu: &GpuMetrics) -> Gauge<'static> {
+    let limit = gpu.power_limit_w.filter(|&w| w > 0.0);
+    let scale = limit.unwrap_or(POWER_GAUGE_FALLBACK_W);
+    let ratio = gpu.power_w.map_or(0.0, |w| (w / scale).clamp(0.0, 1.0) as f64);
+    let power = fmt_dec(gpu.power_w, app.precision);
+    let label = match limit {
+        Some(limit) => format!("Power {power} / {limit:.0} W"),
+        None => format!("Power {power} W (of ~{scale:.0} W, limit unknown)"),
+    };
+    let cue = gauge_style(ratio, &app.thresholds, &app.theme);
+    labeled(app, Gauge::default().gauge_style(cue).ratio(ratio), "Power", accent, cue.label(label))
+}
+
+/// `[ui] thousands_separator` applied to a clock/VRAM/RPM string, if set.
+fn grouped(app: &App, text: String) -> String {
+    match app.thousands_sep {
+        Some(sep) => group_digits(&text, sep),
+        None => text,
+    }
+}
+
+fn vram_text(app: &App, used: Option<u32>, total: Option<u32>) -> String {
+    grouped(app, fmt_vram(used, total, app.vram_unit))
+}
+
+/// "Fan: 1200 RPM", or "Fans: 1200 / 1250 RPM" on multi-fan cards, then
+/// "(auto)" or "(manual)" where the driver says. On AMD, where that comes
+/// from `pwm1_enable`, a card without the node reads "(unknown)".
+fn fan_line(app: &App, gpu: &GpuMetrics) -> String {
+    let rpms: Vec<String> = gpu.fans.iter().map(|f| fixed_int(app, fmt_opt(&f.rpm), RPM_DIGITS)).collect();
+    let line = match rpms.as_slice() {
+        [] => "Fan: -- RPM".into(),
+        [rpm] => format!("Fan: {rpm} RPM"),
+        _ => format!("Fans: {} RPM", rpms.join(" / ")),
+    };
+    match (gpu.fan_control, gpu.vendor) {
+        (Some(control), _) => format!("{line} ({})", control.label()),
+        (None, Vendor::Amd) => format!("{line} (unknown)"),
+        (None, _) => line,
+    }
+}
+
+/// One gauge per fan, meant to share a row; a single "Fan --" when there are none.
+fn fan_gauges(app: &App, accent: Style, gpu: &GpuMetrics) -> Vec<Gauge<'static>> {
+    if gpu.fans.is_empty() {
+        return vec![fan_gauge(app, accent, "Fan", &FanMetrics::default())];
+    }
+    let numbered = gpu.fans.len() > 1;
+    gpu.fans
+        .iter()
+        .map(|fan| {
+            let title = if numbered { format!("Fan {}", fan.index + 1) } else { "Fan".into() };
+            fan_gauge(app, accent, &title, fan)
+        })
+        .collect()
+}
+
+fn fan_gauge(app: &App, accent: Style, title: &str, fan: &FanMetrics) -> Gauge<'static> {
+    let ratio = pct_ratio(fan.pct);
+    let label = match (fan.pct, fan.rpm) {
+        (Some(p), Some(rpm)) => format!("{title} {p:.0}% ({rpm} RPM)"),
+        (Some(p), None) => format!("{title} {p:.0}%"),
+        _ => format!("{title} --"),
+    };
+    let gauge = Gauge::default().gauge_style(gauge_style(ratio, &app.thresholds, &app.theme)).ratio(ratio);
+    labeled(app, gauge, title, accent, grouped(app, label))
+}
+
+/// Current clock against the card's max; `None` when the max isn't known.
+fn clock_gauge(
+    app: &App,
+    accent: Style,
+    title: &str,
+    mhz: Option<u32>,
+    max_mhz: Option<u32>,
+) -> Option<Gauge<'static>> {
+    let max = max_mhz.filter(|&m| m > 0)?;
+    let ratio = mhz.map_or(0.0, |c| (c as f64 / max as f64).clamp(0.0, 1.0));
+    let label = format!("{title} {} / {max} MHz", fmt_opt(&mhz));
+    let gauge = Gauge::default().gauge_style(app.theme.accent.remove_modifier(Modifier::REVERSED)).ratio(ratio);
+    Some(labeled(app, gauge, title, accent, grouped(app, label)))
+}
+
+/// Selected GPU's compute processes, scrolled with j/k.
+fn render_processes(f: &mut ratatui::Frame, app: &App, area: Rect) {
+    let procs = app.metrics.get(app.selected).map(|g| g.processes.as_slice()).unwrap_or_default();
+    let title = format!("GPU {} Processes ({}) — j/k to scroll", app.device_index(app.selected), procs.len());
+    let block = Block::default().borders(Borders::ALL).title(title);
+
+    let lines: Vec<Line> = if procs.is_empty() {
+        vec![Line::from("--")]
+    } else {
+        // Only fdinfo-backed lists have per-process utilization
+        let util = procs.iter().any(|p| p.util_pct.is_some());
+        let util_col = |text: String| if util { format!(" {text:>5}") } else { String::new() };
+        let mut lines = vec![Line::styled(
+            format!("{:>8}  {:<20} {:>10}{}", "PID", "NAME", "VRAM", util_col("UTIL".into())),
+            app.theme.unknown,
+        )];
+        lines.extend(procs.iter().skip(app.process_scroll).map(|p| {
+            let mem = p.used_mb.map(|m| format!("{m} MB")).unwrap_or("--".into());
+            let pct = p.util_pct.map(|u| format!("{u:.0}%")).unwrap_or("--".into());
+            Line::from(format!("{:>8}  {:<20} {:>10}{}", p.pid, p.name, mem, util_col(pct)))
+        }));
+        lines
+    };
+    f.render_widget(Paragraph::new(Text::from(lines)).block(block), area);
+}
+
+/// Selected GPU's utilization history. Only the newest samples that fit are drawn,
+/// so it scrolls once full and just starts at the left edge while history is still short.
+fn render_util_sparkline(f: &mut ratatui::Frame, app: &App, area: Rect) {
+    let smoothed = if app.smooth_util { format!(" (avg of {})", app.smoothing) } else { String::new() };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("GPU {} Utilization History{smoothed}", app.device_index(app.selected)));
+    let width = block.inner(area).width as usize;
+    let data: Vec<u64> = app
+        .selected_history()
+        .map(|h| {
+            let util = if app.smooth_util { h.util_smoothed(app.smoothing) } else { h.util.iter().copied().collect() };
+            let skip = util.len().saturating_sub(width);
+            util.iter().skip(skip).map(|u| u.clamp(0.0, 100.0) as u64).collect()
+        })
+        .unwrap_or_default();
+    let latest = pct_ratio(app.shown_util(app.selected));
+    let sparkline = Sparkline::default()
+        .block(block)
+        .data(&data)
+        .max(100)
+        .style(gauge_style(latest, &app.thresholds, &app.theme));
+    f.render_widget(sparkline, area);
+}
+
+/// Selected GPU's power draw history. Scaled to at least the critical power
+/// threshold so a mostly idle card doesn't look pegged.
+fn render_power_sparkline(f: &mut ratatui::Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("GPU {} Power History", app.device_index(app.selected)));
+    let width = block.inner(area).width as usize;
+    let power = app.selected_history().map(|h| &h.power);
+    let data: Vec<u64> = power
+        .map(|p| {
+            let skip = p.len().saturating_sub(width);
+            p.iter().skip(skip).map(|w| w.max(0.0).round() as u64).collect()
+        })
+        .unwrap_or_default();
+    let max = data.iter().copied().max().unwrap_or(0).max(app.thresholds.power.crit as u64);
+    let latest = power.and_then(|p| p.back().copied());
+    let sparkline = Sparkline::default()
+        .block(block)
+        .data(&data)
+        .max(max.max(1))
+        .style(power_style(latest, &app.thresholds, &app.theme));
+    f.render_widget(sparkline, area);
+}
+
+/// Selected GPU's VRAM in use, scaled to the card's total so the height is
+/// how full it is; a steady climb here is what a leak looks like.
+fn render_vram_sparkline(f: &mut ratatui::Frame, app: &App, area: Rect) {
+    let gpu = app.metrics.get(app.selected);
+    let total = gpu.and_then(|g| g.vram_total_mb);
+    let title = match gpu {
+        Some(g) => format!(
+            "GPU {} VRAM History ({})",
+            app.device_index(app.selected),
+            vram_text(app, g.vram_used_mb, total)
+        ),
+        None => format!("GPU {} VRAM History", app.device_index(app.selected)),
+    };
+    let block = Block::default().borders(Borders::ALL).title(title);
+    let width = block.inner(area).width as usize;
+    let data: Vec<u64> = app
+        .selected_history()
+        .map(|h| {
+            let skip = h.vram.len().saturating_sub(width);
+            h.vram.iter().skip(skip).map(|&mb| u64::from(mb)).collect()
+        })
+        .unwrap_or_default();
+    // Without a total, the biggest reading so far is the best scale there is
+    let max = total.map(u64::from).unwrap_or_else(|| data.iter().copied().max().unwrap_or(0));
+    let style = match total {
+        Some(_) => vram_style(vram_ratio(gpu.and_then(|g| g.vram_used_mb), total), &app.thresholds, &app.theme).style,
+        None => app.theme.unknown,
+    };
+    let sparkline = Sparkline::default().block(block).data(&data).max(max.max(1)).style(style);
+    f.render_widget(sparkline, area);
+}
+
+/// Selected GPU's core clock, scaled to its max clock when known. Samples
+/// without a reading draw nothing, leaving a gap instead of a dive to zero.
+fn render_clock_sparkline(f: &mut ratatui::Frame, app: &App, area: Rect) {
+    let gpu = app.metrics.get(app.selected);
+    let now = grouped(
+        app,
+        format!(
+            "core {} MHz, mem {} MHz",
+            fmt_opt(&gpu.and_then(|g| g.core_clock_mhz)),
+            fmt_opt(&gpu.and_then(|g| g.mem_clock_mhz))
+        ),
+    );
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("GPU {} Core Clock History ({now})", app.device_index(app.selected)));
+    let width = block.inner(area).width as usize;
+    let data: Vec<u64> = app
+        .selected_history()
+        .map(|h| {
+            let skip = h.core_clock.len().saturating_sub(width);
+            h.core_clock.iter().skip(skip).map(|&mhz| mhz.map_or(0, u64::from)).collect()
+        })
+        .unwrap_or_default();
+    let max = gpu
+        .and_then(|g| g.core_clock_max_mhz)
+        .map(u64::from)
+        .unwrap_or_else(|| data.iter().copied().max().unwrap_or(0));
+    let sparkline = Sparkline::default()
+        .block(block)
+        .data(&data)
+        .max(max.max(1))
+        .style(app.theme.accent.remove_modifier(Modifier::REVERSED));
+    f.render_widget(sparkline, area);
+}
+
+/// Selected GPU's core temp over the last `history_window`. X is seconds relative
+/// to now (-window..0); the line takes the thermal color of the newest point.
+fn render_temp_chart(f: &mut ratatui::Frame, app: &App, area: Rect) {
+    let unit = app.temp_unit;
+    let window = app.history_window.as_secs_f64();
+    let now = Instant::now();
+    let temps = app.selected_history().map(|h| &h.temp);
+
+    let points: Vec<(f64, f64)> = temps
+        .into_iter()
+        .flatten()
+        .map(|&(t, c)| (-(now - t).as_secs_f64(), unit.convert(c) as f64))
+        .collect();
+    let latest = temps.and_then(|t| t.back()).map(|&(_, c)| unit.convert(c));
+
+    // Fixed 0..100°C unless the card runs hotter than that
+    let y_max = points.iter().map(|&(_, y)| y).fold(unit.convert(100.0) as f64, f64::max);
+    let y_min = unit.convert(0.0) as f64;
+
+    let dataset = Dataset::default()
+        .marker(Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(temp_style(latest, unit, &app.thresholds, &app.theme))
+        .data(&points);
+
+    let chart = Chart::new(vec![dataset])
+        .block(Block::default().borders(Borders::ALL).title(format!("GPU {} Temperature History", app.device_index(app.selected))))
+        .x_axis(
+            Axis::default()
+                .bounds([-window, 0.0])
+                .labels(vec![Span::raw(format!("-{window:.0}s")), Span::raw("now")]),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([y_min, y_max])
+                .labels(vec![
+                    Span::raw(format!("{y_min:.0}")),
+                    Span::raw(format!("{y_max:.0}{}", unit.suffix())),
+                ]),
+        );
+    f.render_widget(chart, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{Terminal, backend::TestBackend};
+
+    use super::*;
+    use crate::metrics::FanControl;
+    use crate::style::TempUnit;
+
+    /// Renders one frame and returns it as text, one line per row.
+    fn render(app: &App, width: u16, height: u16) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|f| ui(f, app)).unwrap();
+        let buf = terminal.backend().buffer();
+        (0..buf.area.height)
+            .map(|y| (0..buf.area.width).map(|x| buf.get(x, y).symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn test_gpu() -> GpuMetrics {
+        GpuMetrics {
+            name: "Test GPU".into(),
+            temperature_c: Some(65.5),
+            utilization_pct: Some(42.0),
+            power_w: Some(120.0),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn panel_shows_the_known_readings() {
+        let app = App::with_metrics(vec![test_gpu()]);
+        let screen = render(&app, 100, 40);
+        assert!(screen.contains("GPU 0: Test GPU"), "{screen}");
+        assert!(screen.contains("Temp:  65.5 °C"), "{screen}");
+        assert!(screen.contains("Power:  120.0 W"), "{screen}");
+        assert!(screen.contains("GPU Util 42%"), "{screen}");
+    }
+
+    #[test]
+    fn temps_follow_the_unit_and_precision() {
+        let mut app = App::with_metrics(vec![test_gpu()]);
+        app.temp_unit = TempUnit::Fahrenheit;
+        app.precision = 0;
+        let screen = render(&app, 100, 40);
+        assert!(screen.contains("Temp: 150 °F"), "{screen}");
+    }
+
+    #[test]
+    fn throttle_reasons_get_their_own_line() {
+        let gpu = GpuMetrics { throttle_reasons: vec!["thermal".into(), "power".into()], ..test_gpu() };
+        let screen = render(&App::with_metrics(vec![gpu]), 100, 40);
+        assert!(screen.contains("THROTTLING: thermal, power"), "{screen}");
+    }
+
+    #[test]
+    fn hidden_gauges_leave_no_empty_row() {
+        let mut app = App::with_metrics(vec![test_gpu()]);
+        app.gauges.toggle(GaugeKind::Vram);
+        let screen = render(&app, 100, 40);
+        assert!(screen.contains("GPU Util 42%") && !screen.contains("VRAM Usage"), "{screen}");
+    }
+
+    #[test]
+    fn vram_without_a_total_is_labelled_unknown() {
+        let gpu = GpuMetrics { vram_used_mb: Some(800), ..test_gpu() };
+        let screen = render(&App::with_metrics(vec![gpu]), 100, 40);
+        assert!(screen.contains("VRAM 800 MB / unknown"), "{screen}");
+    }
+
+    #[test]
+    fn long_names_are_cut_to_the_panel_with_the_full_name_in_help() {
+        let name = "NVIDIA GeForce RTX 4090 Laptop GPU";
+        let gpus = vec![GpuMetrics { name: name.into(), ..test_gpu() }, test_gpu()];
+        let mut app = App::with_metrics(gpus);
+        let screen = render(&app, 80, 40);
+        assert!(screen.contains("GPU 0: NVIDIA GeForce RTX 4090 Lapto…┐"), "{screen}");
+
+        app.show_help = true;
+        let screen = render(&app, 120, 40);
+        assert!(screen.contains(&format!("GPU 0: {name}")), "{screen}");
+    }
+
+    #[test]
+    fn readings_keep_their_column_as_digits_change() {
+        let gpu = |w: f32, mhz: u32| GpuMetrics { power_w: Some(w), core_clock_mhz: Some(mhz), ..test_gpu() };
+        let screen = render(&App::with_metrics(vec![gpu(9.0, 950)]), 100, 40);
+        assert!(screen.contains("Power:    9.0 W") && screen.contains("core  950 MHz"), "{screen}");
+        let screen = render(&App::with_metrics(vec![gpu(100.0, 1800)]), 100, 40);
+        assert!(screen.contains("Power:  100.0 W") && screen.contains("core 1800 MHz"), "{screen}");
+    }
+
+    #[test]
+    fn no_gpus_explains_itself_instead_of_empty_widgets() {
+        let mut app = App::with_metrics(vec![]);
+        let screen = render(&app, 100, 40);
+        assert!(screen.contains(NO_GPUS), "{screen}");
+        assert!(!screen.contains("Utilization History"), "{screen}");
+
+        app.compact = true;
+        assert!(render(&app, 100, 10).contains(NO_GPUS));
+    }
+
+    #[test]
+    fn mixed_vendors_are_tagged_and_summed_per_vendor() {
+        let nvidia = GpuMetrics { vendor: Vendor::Nvidia, power_w: Some(300.0), ..test_gpu() };
+        let amd = GpuMetrics { vendor: Vendor::Amd, power_w: Some(100.0), ..test_gpu() };
+        let app = App::with_metrics(vec![nvidia.clone(), amd, nvidia]);
+        let screen = render(&app, 160, 50);
+        assert!(screen.contains("All 3 GPUs: VRAM --"), "{screen}");
+        assert!(screen.contains("NVIDIA 2 GPUs: VRAM --"), "{screen}");
+        assert!(screen.contains("AMD 1 GPU: VRAM --") && screen.contains("power 100.0 W"), "{screen}");
+        assert!(screen.contains("GPU 1 [AMD]: Test GPU"), "{screen}");
+    }
+
+    #[test]
+    fn compact_rows_take_the_configured_separator() {
+        let mut app = App::with_metrics(vec![test_gpu(); 4]);
+        app.compact = true;
+        let screen = render(&app, 100, 12);
+        assert!((0..4).all(|i| screen.contains(&format!("{i} Test GPU"))), "{screen}");
+        assert!(!screen.contains("──────"), "{screen}");
+
+        app.separator = GpuSeparator::Rule;
+        let screen = render(&app, 100, 40);
+        let rows: Vec<&str> = screen.lines().skip_while(|l| !l.starts_with("0 Test GPU")).take(3).collect();
+        assert!(rows[1].trim_end().chars().all(|c| c == '─') && rows[2].starts_with("1 Test GPU"), "{screen}");
+    }
+
+    #[test]
+    fn compact_mode_keeps_a_status_row() {
+        let mut app = App::with_metrics(vec![GpuMetrics { temperature_c: Some(61.26), ..test_gpu() }; 2]);
+        app.compact = true;
+        app.precision = 2;
+        let screen = render(&app, 100, 6);
+        assert!(screen.contains("61.26"), "{screen}");
+        assert!(screen.lines().last().unwrap().starts_with("q to quit"), "{screen}");
+
+        app.paused = true;
+        app.status = Some("NVML: GPU lost".into());
+        let screen = render(&app, 100, 6);
+        let last = screen.lines().last().unwrap();
+        assert!(last.starts_with("PAUSED for") && last.contains("NVML: GPU lost"), "{screen}");
+    }
+
+    #[test]
+    fn clipped_panel_text_scrolls_with_the_arrow_keys() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let mut app = App::with_metrics(vec![test_gpu()]);
+        // The scrollbar takes over the panel's right border beside the text
+        let border = |screen: &str| screen.lines().map(|l| l.chars().nth(98).unwrap()).collect::<String>();
+        let screen = render(&app, 100, 26);
+        assert!(screen.contains("Temp:") && border(&screen).contains('█'), "{screen}");
+        let (overflow, _) = app.body_overflow.get();
+        assert!(overflow > 0);
+
+        for _ in 0..overflow + 3 {
+            app.on_key(KeyEvent::new(KeyCode::Down, KeyModifiers::NONE));
+        }
+        assert_eq!(app.body_scroll, overflow);
+        let screen = render(&app, 100, 26);
+        assert!(!screen.contains("Temp:"), "{screen}");
+
+        app.on_key(KeyEvent::new(KeyCode::PageUp, KeyModifiers::NONE));
+        app.on_key(KeyEvent::new(KeyCode::PageUp, KeyModifiers::NONE));
+        assert_eq!(app.body_scroll, 0);
+    }
+
+    #[test]
+    fn gauge_readings_move_to_the_title_or_go_away() {
+        let mut app = App::with_metrics(vec![test_gpu()]);
+        let screen = render(&app, 100, 40);
+        assert!(screen.contains("┌Utilization") && screen.contains("GPU Util"), "{screen}");
+
+        app.gauge_labels = GaugeLabels::Title;
+        let screen = render(&app, 100, 40);
+        assert!(screen.contains("┌GPU Util") && !screen.contains("┌Utilization"), "{screen}");
+        assert_eq!(screen.matches("GPU Util").count(), 1, "{screen}");
+
+        app.gauge_labels = GaugeLabels::Off;
+        let screen = render(&app, 100, 40);
+        assert!(screen.contains("┌Utilization") && !screen.contains("GPU Util"), "{screen}");
+    }
+
+    #[test]
+    fn chip_power_is_shown_apart_from_board_power() {
+        let screen = render(&App::with_metrics(vec![test_gpu()]), 100, 40);
+        assert!(!screen.contains("Chip power") && !screen.contains("Board power"), "{screen}");
+
+        let gpu = GpuMetrics { gpu_chip_power_w: Some(95.0), ..test_gpu() };
+        let screen = render(&App::with_metrics(vec![gpu]), 100, 40);
+        assert!(screen.contains("Board power:  120.0 W") && screen.contains("Chip power:   95.0 W"), "{screen}");
+    }
+
+    #[test]
+    fn engine_breakdown_sits_under_the_utilization_gauge() {
+        let engines = |names: &[&str]| names.iter().map(|n| (n.to_string(), 40.0)).collect();
+        let gpu = GpuMetrics { engine_utilization: engines(&["render", "video"]), ..test_gpu() };
+        let screen = render(&App::with_metrics(vec![gpu]), 100, 80);
+        assert!(screen.contains("┌Engines") && screen.contains("render  40%"), "{screen}");
+        assert!(screen.contains("video   40%"), "{screen}");
+
+        // One engine is what the utilization gauge already shows
+        let gpu = GpuMetrics { engine_utilization: engines(&["render"]), ..test_gpu() };
+        let screen = render(&App::with_metrics(vec![gpu]), 100, 80);
+        assert!(!screen.contains("Engines"), "{screen}");
+    }
+
+    #[test]
+    fn fan_line_says_who_controls_the_fan() {
+        let app = App::with_metrics(vec![]);
+        assert_eq!(fan_line(&app, &test_gpu()), "Fan: -- RPM");
+
+        let manual = GpuMetrics { vendor: Vendor::Amd, fan_control: Some(FanControl::Manual), ..test_gpu() };
+        assert_eq!(fan_line(&app, &manual), "Fan: -- RPM (manual)");
+        let unknown = GpuMetrics { vendor: Vendor::Amd, ..test_gpu() };
+        assert_eq!(fan_line(&app, &unknown), "Fan: -- RPM (unknown)");
+    }
+
+    #[test]
+    fn tiny_terminal_gets_a_message_instead_of_a_layout() {
+        let app = App::with_metrics(vec![test_gpu()]);
+        let screen = render(&app, 30, 10);
+        assert!(screen.contains("Terminal too small"), "{screen}");
+    }
+}